@@ -1,7 +1,10 @@
 use softui::*;
+use std::{cell::Cell, rc::Rc};
 
 fn main() {
     let ctx = create_ctx("Softui", 800, 600);
+    let position = Rc::new(Cell::new((50, 50)));
+    let dropped = Rc::new(Cell::new(0));
 
     loop {
         match ctx.event() {
@@ -9,42 +12,37 @@ fn main() {
             _ => {}
         }
 
-        //Dragging example.
-        if ctx.window.left_mouse.inital_position != Rect::default() {
-            let inital = ctx.window.left_mouse.inital_position;
-            let end = ctx
-                .window
-                .left_mouse
-                .release_position
-                .unwrap_or(ctx.window.mouse_position);
-            let mut drag = Rect::default();
+        //Move the box around, a copy follows the mouse while it's dragged.
+        let (x, y) = position.get();
+        let moved = position.clone();
+        let mut source = Basic::default()
+            .pos(x, y, 100, 100)
+            .drag_payload(dropped.get())
+            .drag_preview()
+            .on_drag_start(|_| println!("Drag started"))
+            .on_drag(move |_, (dx, dy)| {
+                let (x, y) = moved.get();
+                moved.set(((x as isize + dx).max(0) as usize, (y as isize + dy).max(0) as usize));
+            })
+            .on_drag_end(|_| println!("Drag ended"));
 
-            if end.x > inital.x {
-                drag.x = inital.x;
-                drag.width = end.x - inital.x;
-            } else {
-                drag.x = end.x;
-                drag.width = inital.x - end.x;
-            }
+        //Only even payloads can be dropped here.
+        let mut target = Basic::default()
+            .pos(500, 200, 200, 200)
+            .accepts(|count: &usize| count.is_multiple_of(2))
+            .on_drop({
+                let dropped = dropped.clone();
+                move |_, count: &usize| {
+                    dropped.set(dropped.get() + 1);
+                    println!("Dropped {} times, payload: {}", dropped.get(), count);
+                }
+            });
 
-            if end.y > inital.y {
-                drag.y = inital.y;
-                drag.height = end.y - inital.y;
-            } else {
-                drag.y = end.y;
-                drag.height = inital.y - end.y;
-            }
+        target.try_click();
+        queue_command(target.area, target.primative());
+        source.try_click();
+        queue_command(source.area, source.primative());
 
-            ctx.draw_rectangle(
-                drag.x as usize,
-                drag.y as usize,
-                drag.width as usize,
-                drag.height as usize,
-                red(),
-            );
-        }
-
-        //Draw all queue commands.
         ctx.draw_frame();
     }
 }
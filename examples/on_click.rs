@@ -1,4 +1,5 @@
 use softui::*;
+use std::{cell::Cell, rc::Rc};
 
 fn main() {
     let ctx = create_ctx("Softui", 800, 600);
@@ -11,6 +12,8 @@ fn main() {
         .on_click(Middle, |_| println!("Middle"))
         .on_click(Right, |_| println!("Right"));
 
+    let count = Rc::new(Cell::new(0));
+
     loop {
         match ctx.event() {
             Some(Event::Quit | Event::Input(Key::Escape, _)) => break,
@@ -20,6 +23,16 @@ fn main() {
         example.try_click();
         queue_command(example.area, example.primative());
 
+        //Handlers run after the layout is dropped, so state they change is shared with them instead of borrowed.
+        flex!(v!(Basic::default().wh(100).on_click(Left, {
+            let count = count.clone();
+            move |_| {
+                count.set(count.get() + 1);
+                println!("Clicked {} times", count.get());
+            }
+        })))
+        .padding(200);
+
         ctx.draw_frame();
     }
 }
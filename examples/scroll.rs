@@ -0,0 +1,28 @@
+use softui::*;
+
+fn main() {
+    let ctx = create_ctx("Softui", 800, 600);
+
+    loop {
+        match ctx.event() {
+            Some(Event::Quit | Event::Input(Key::Escape, _)) => break,
+            _ => {}
+        }
+
+        scroll!(
+            rect().bg(red()).wh(300),
+            rect().bg(lime()).wh(300),
+            rect().bg(blue()).w(600).h(300),
+            text("The end")
+        )
+        .gap(16)
+        .padding(16)
+        .x(32)
+        .y(32)
+        .w(400)
+        .h(500)
+        .bg(gray());
+
+        ctx.draw_frame();
+    }
+}
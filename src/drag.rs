@@ -0,0 +1,208 @@
+//! Dragging widgets with the left mouse and dropping them onto other widgets.
+//!
+//! ```rs
+//! Basic::default()
+//!     .drag_payload(index)
+//!     .drag_preview()
+//!     .on_drag(|_, (x, y)| println!("Moved {x} {y}"));
+//!
+//! Basic::default()
+//!     .accepts(|index: &usize| *index != 0)
+//!     .on_drop(move |_, index: &usize| items.borrow_mut().swap(0, *index));
+//! ```
+//!
+//! The state of the drag is worked out at the end of every frame, each step is seen by widgets for one frame.
+//! Draggable widgets are told apart by the order they're drawn in, so the list shouldn't change during a drag.
+use crate::*;
+use std::{any::Any, sync::Arc};
+
+/// How far the mouse has to move while pressed before a drag starts.
+pub const DRAG_THRESHOLD: usize = 4;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum DragPhase {
+    #[default]
+    None,
+    /// Pressed but still inside of the threshold.
+    Pending,
+    Started,
+    Dragging,
+    Ended,
+}
+
+#[derive(Default)]
+pub struct DragState {
+    pub phase: DragPhase,
+    /// Draggable widgets registered this frame.
+    pub areas: Vec<Rect>,
+    /// Draggable widgets registered last frame, in the order they were drawn.
+    pub previous: Vec<Rect>,
+    /// Where the mouse was pressed.
+    pub origin: Option<Rect>,
+    /// The widget being dragged, the index of it in `previous`.
+    pub source: Option<usize>,
+    /// Where the source was last drawn, it can't be dropped onto itself.
+    pub source_area: Option<Rect>,
+    /// The topmost widget under the mouse when the drag ended.
+    pub target: Option<HoverId>,
+    pub payload: Option<Arc<dyn Any + Send + Sync>>,
+    /// Set by `accepts` before the drop handlers of a widget are run.
+    pub accepted: bool,
+    /// Mouse movement since the last frame.
+    pub delta: (isize, isize),
+    pub mouse: Rect,
+    /// Drawn on top of everything else and moved with the mouse.
+    pub preview: Option<Command>,
+}
+
+impl std::fmt::Debug for DragState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DragState")
+            .field("phase", &self.phase)
+            .field("origin", &self.origin)
+            .field("source", &self.source)
+            .field("target", &self.target)
+            .field("delta", &self.delta)
+            .finish()
+    }
+}
+
+impl DragState {
+    /// Returns the index used to find the source of the drag.
+    pub fn register(&mut self, area: Rect) -> usize {
+        self.areas.push(area);
+        self.areas.len() - 1
+    }
+
+    pub fn is_active(&self) -> bool {
+        matches!(self.phase, DragPhase::Started | DragPhase::Dragging)
+    }
+
+    /// Distance from where the mouse was pressed.
+    pub fn offset(&self) -> (isize, isize) {
+        match self.origin {
+            Some(origin) => (
+                self.mouse.x as isize - origin.x as isize,
+                self.mouse.y as isize - origin.y as isize,
+            ),
+            None => (0, 0),
+        }
+    }
+
+    pub fn payload<P: 'static>(&self) -> Option<&P> {
+        self.payload.as_ref()?.downcast_ref()
+    }
+
+    /// Called once all of the widgets have been drawn, after the hover state is updated.
+    pub fn next_frame(&mut self, mouse: Rect, down: bool, hover: &HoverState) {
+        self.previous = std::mem::take(&mut self.areas);
+        self.delta = (
+            mouse.x as isize - self.mouse.x as isize,
+            mouse.y as isize - self.mouse.y as isize,
+        );
+        self.mouse = mouse;
+
+        if self.phase == DragPhase::Ended {
+            self.phase = DragPhase::None;
+        }
+
+        self.phase = match (self.phase, down) {
+            (DragPhase::None, true) => {
+                //Only the topmost widget can be dragged.
+                let top = hover.top(mouse);
+                self.origin = Some(mouse);
+                self.source = top.and_then(|(_, top)| self.previous.iter().rposition(|area| *area == top));
+                self.payload = None;
+                self.source_area = None;
+                self.target = None;
+                DragPhase::Pending
+            }
+            (DragPhase::Pending, true) => {
+                let (x, y) = self.offset();
+                if self.source.is_some() && x * x + y * y > (DRAG_THRESHOLD * DRAG_THRESHOLD) as isize {
+                    DragPhase::Started
+                } else {
+                    DragPhase::Pending
+                }
+            }
+            (DragPhase::Started | DragPhase::Dragging, true) => DragPhase::Dragging,
+            (DragPhase::Started | DragPhase::Dragging, false) => {
+                self.target = hover
+                    .previous
+                    .iter()
+                    .rev()
+                    .find(|(_, area)| area.intersects(mouse) && Some(*area) != self.source_area)
+                    .map(|(id, _)| *id);
+                DragPhase::Ended
+            }
+            (_, false) => {
+                self.origin = None;
+                self.source = None;
+                DragPhase::None
+            }
+            (phase, true) => phase,
+        };
+    }
+}
+
+/// The preview is drawn where the source was, moved by how far the mouse has been dragged.
+pub fn preview_area(drag: &DragState, area: Rect) -> Rect {
+    let (x, y) = drag.offset();
+    Rect::new(
+        (area.x as isize + x).max(0) as usize,
+        (area.y as isize + y).max(0) as usize,
+        area.width,
+        area.height,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn drag() {
+        let mut hover = HoverState::default();
+        let mut drag = DragState::default();
+        let (source, target) = (Rect::new(0, 0, 10, 10), Rect::new(50, 0, 10, 10));
+        let ids = (("source", 0, 0, 0), ("target", 0, 0, 0));
+        let frame = |hover: &mut HoverState, drag: &mut DragState, mouse: Rect, down: bool| {
+            hover.register(ids.0, source);
+            hover.register(ids.1, target);
+            drag.register(source);
+            drag.source_area = drag.source.map(|_| source);
+            hover.next_frame(mouse);
+            drag.next_frame(mouse, down, hover);
+        };
+
+        frame(&mut hover, &mut drag, Rect::new(5, 5, 1, 1), false);
+        frame(&mut hover, &mut drag, Rect::new(5, 5, 1, 1), true);
+        assert_eq!(drag.phase, DragPhase::Pending);
+        assert_eq!(drag.source, Some(0));
+
+        //Inside of the dead zone.
+        frame(&mut hover, &mut drag, Rect::new(7, 6, 1, 1), true);
+        assert_eq!(drag.phase, DragPhase::Pending);
+
+        frame(&mut hover, &mut drag, Rect::new(20, 5, 1, 1), true);
+        assert_eq!(drag.phase, DragPhase::Started);
+        assert_eq!(drag.delta, (13, -1));
+        assert_eq!(drag.offset(), (15, 0));
+        assert_eq!(preview_area(&drag, source), Rect::new(15, 0, 10, 10));
+
+        frame(&mut hover, &mut drag, Rect::new(55, 5, 1, 1), true);
+        assert_eq!(drag.phase, DragPhase::Dragging);
+
+        frame(&mut hover, &mut drag, Rect::new(55, 5, 1, 1), false);
+        assert_eq!(drag.phase, DragPhase::Ended);
+        assert_eq!(drag.target, Some((ids.1, 0)));
+
+        frame(&mut hover, &mut drag, Rect::new(55, 5, 1, 1), false);
+        assert_eq!(drag.phase, DragPhase::None);
+
+        //Letting go inside of the dead zone is just a click.
+        frame(&mut hover, &mut drag, Rect::new(5, 5, 1, 1), true);
+        frame(&mut hover, &mut drag, Rect::new(5, 5, 1, 1), false);
+        assert_eq!(drag.phase, DragPhase::None);
+    }
+}
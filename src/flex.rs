@@ -10,7 +10,25 @@ pub enum FlexDirection {
     BottomTop,
 }
 
-#[derive(Default, Debug, Clone, Copy)]
+impl FlexDirection {
+    /// The same axis, laid out from the other end.
+    pub const fn reverse(self) -> Self {
+        match self {
+            FlexDirection::LeftRight => FlexDirection::RightLeft,
+            FlexDirection::RightLeft => FlexDirection::LeftRight,
+            FlexDirection::TopBottom => FlexDirection::BottomTop,
+            FlexDirection::BottomTop => FlexDirection::TopBottom,
+        }
+    }
+    pub const fn is_reversed(self) -> bool {
+        matches!(self, FlexDirection::RightLeft | FlexDirection::BottomTop)
+    }
+    pub const fn is_horizontal(self) -> bool {
+        matches!(self, FlexDirection::LeftRight | FlexDirection::RightLeft)
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct Padding {
     pub left: usize,
     pub top: usize,
@@ -33,8 +51,29 @@ impl Padding {
             bottom,
         }
     }
+    /// (main, cross) space before the content.
+    #[inline]
+    pub const fn start(&self, horizontal: bool) -> (usize, usize) {
+        if horizontal {
+            (self.left, self.top)
+        } else {
+            (self.top, self.left)
+        }
+    }
+    /// (main, cross) space on both sides added together.
+    #[inline]
+    pub const fn total(&self, horizontal: bool) -> (usize, usize) {
+        if horizontal {
+            (self.left + self.right, self.top + self.bottom)
+        } else {
+            (self.top + self.bottom, self.left + self.right)
+        }
+    }
 }
 
+/// Space around the outside of a widget.
+pub type Margin = Padding;
+
 // Positioning Step
 // +---------------------------------+
 // | +-------------+ +-------------+ |
@@ -60,8 +99,7 @@ impl Padding {
 //The parent size will be padding.left + container.width + padding.right
 
 //Because of the way macros work, the flex macro will need to also do sizing
-//I can't chain together v!() and h!() without something delimiting the root node.
-//There might be a way but I've been working on this for too long it's time to LOCK IN.
+//Nested containers, widgets and other roots are all turned into a `TypelessWidget` by `Child`.
 
 pub fn calculate_offset(direction: FlexDirection, padding: Padding) -> usize {
     match direction {
@@ -72,121 +110,526 @@ pub fn calculate_offset(direction: FlexDirection, padding: Padding) -> usize {
     }
 }
 
-pub fn draw_widgets(
-    commands: &mut Vec<Command>,
-    root_direction: FlexDirection,
-    container: &mut Container,
-    x_offset: &mut usize,
-    y_offset: &mut usize,
-    padding: Padding,
-    gap: usize,
-) {
-    let last_index = container.widgets.len().saturating_sub(1);
-    //Add the gap between the two containers to next widget
-    for (i, widget) in container.widgets.iter_mut().enumerate() {
-        let mut area = widget.area.clone();
-        // dbg!(container.direction);
+/// How a child of a container is sized on the main axis, the same as CSS flexbox.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlexItem {
+    pub grow: f32,
+    pub shrink: f32,
+    /// The width or height of the widget is used when `None`.
+    pub basis: Option<usize>,
+    /// Percentage of the container's main size, see `resolve_basis`.
+    pub relative_basis: Option<f32>,
+    pub margin: Margin,
+    /// Only used inside a `grid!`.
+    pub cell: GridCell,
+    /// Percentage of the parent, set with `Unit::Percentage`.
+    pub relative_width: Option<f32>,
+    pub relative_height: Option<f32>,
+    pub position: Position,
+    pub inset: Inset,
+    pub min_width: Option<usize>,
+    pub max_width: Option<usize>,
+    pub min_height: Option<usize>,
+    pub max_height: Option<usize>,
+    /// Width divided by height.
+    pub aspect_ratio: Option<f32>,
+    /// Overrides `align_items` on the cross axis, or vertically in a `stack!` and `grid!`.
+    pub align_self: Option<AlignItems>,
+    /// Horizontal alignment inside a `stack!` and `grid!`.
+    pub justify_self: Option<AlignItems>,
+}
 
-        area.x = *x_offset;
-        area.y = *y_offset;
+impl FlexItem {
+    pub const fn new() -> Self {
+        Self {
+            grow: 0.0,
+            shrink: 1.0,
+            basis: None,
+            relative_basis: None,
+            margin: Padding::new(0, 0, 0, 0),
+            cell: GridCell::new(),
+            relative_width: None,
+            relative_height: None,
+            position: Position::Relative,
+            inset: Inset::new(),
+            min_width: None,
+            max_width: None,
+            min_height: None,
+            max_height: None,
+            aspect_ratio: None,
+            align_self: None,
+            justify_self: None,
+        }
+    }
+    /// Images keep the ratio of their bitmap.
+    pub const fn aspect_ratio(mut self, width: usize, height: usize) -> Self {
+        if height != 0 {
+            self.aspect_ratio = Some(width as f32 / height as f32);
+        }
+        self
+    }
+    /// Keep a width or height between the min and max, the min wins when they overlap.
+    pub fn clamp(&self, length: usize, horizontal: bool) -> usize {
+        let (min, max) = if horizontal {
+            (self.min_width, self.max_width)
+        } else {
+            (self.min_height, self.max_height)
+        };
+        let length = max.map_or(length, |max| length.min(max));
+        min.map_or(length, |min| length.max(min))
+    }
+    /// Size the other axis from the aspect ratio and clamp both.
+    /// `horizontal` is the axis that was just set.
+    pub fn constrain(&self, area: &mut Rect, horizontal: bool) {
+        if let Some(ratio) = self.aspect_ratio.filter(|r| *r > 0.0) {
+            if horizontal {
+                area.height = (area.width as f32 / ratio).round() as usize;
+            } else {
+                area.width = (area.height as f32 * ratio).round() as usize;
+            }
+        }
+        area.width = self.clamp(area.width, true);
+        area.height = self.clamp(area.height, false);
+    }
+    /// Absolute items don't take up any space in the container.
+    #[inline]
+    pub const fn is_absolute(&self) -> bool {
+        matches!(self.position, Position::Absolute)
+    }
+    /// The size before any free space is shared out.
+    #[inline]
+    pub fn hypothetical(&self, area: Rect, horizontal: bool) -> usize {
+        self.clamp(self.basis.unwrap_or(main_cross(area, horizontal).0), horizontal)
+    }
+}
 
-        match container.direction {
-            FlexDirection::LeftRight => area.y += padding.top,
-            FlexDirection::RightLeft => todo!(),
-            FlexDirection::TopBottom => area.x += padding.left,
-            FlexDirection::BottomTop => todo!(),
+impl Default for FlexItem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Grow or shrink every item so they fill `available`.
+/// Free space is shared by `grow`, overflow is taken away by `shrink * basis`.
+/// Items that would shrink below zero or go past their min or max are frozen and the rest is shared again.
+pub fn flex_sizes(bases: &[usize], items: &[FlexItem], available: usize, horizontal: bool) -> Vec<usize> {
+    let total: usize = bases.iter().sum();
+    if total == available {
+        return bases.to_vec();
+    }
+
+    let growing = available > total;
+    let mut sizes: Vec<f32> = bases.iter().map(|b| *b as f32).collect();
+    let mut frozen = vec![false; bases.len()];
+
+    loop {
+        let used: f32 = (0..bases.len())
+            .map(|i| if frozen[i] { sizes[i] } else { bases[i] as f32 })
+            .sum();
+        let free = available as f32 - used;
+        let weight = |i: usize| match growing {
+            true => items[i].grow,
+            false => items[i].shrink * bases[i] as f32,
+        };
+        let total_weight: f32 = (0..bases.len()).filter(|i| !frozen[*i]).map(weight).sum();
+        if total_weight <= 0.0 {
+            break;
         }
 
-        // This will always fail since TypelessWidget doesn't implement try_click :(
-        // widget.area = area;
-        // widget.try_click();
-        // widget.run_click(area);
+        let mut clamped = false;
+        for i in 0..bases.len() {
+            if frozen[i] {
+                continue;
+            }
+            let size = bases[i] as f32 + free * weight(i) / total_weight;
+            let rounded = size.max(0.0).round();
+            let limited = items[i].clamp(rounded as usize, horizontal) as f32;
+            if size < 0.0 || limited != rounded {
+                sizes[i] = limited;
+                frozen[i] = true;
+                clamped = true;
+            } else {
+                sizes[i] = size;
+            }
+        }
+
+        if !clamped {
+            break;
+        }
+    }
+
+    sizes.into_iter().map(|s| s.round() as usize).collect()
+}
+
+/// Where children sit on the cross axis, vertically in a `h!` and horizontally in a `v!`.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum AlignItems {
+    #[default]
+    Start,
+    Center,
+    End,
+    /// Children are resized to fill the cross axis.
+    Stretch,
+    /// Line up the first baseline of every text widget, only works in rows.
+    Baseline,
+}
 
+/// How free space on the main axis is shared between the children.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum JustifyContent {
+    /// Follows the direction, the right edge of a `RightLeft` row.
+    #[default]
+    Start,
+    Center,
+    /// The left edge of a `RightLeft` row and the top of a `BottomTop` column.
+    End,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+}
+
+impl JustifyContent {
+    /// Reversed containers are laid out backwards from the end of the main axis.
+    pub const fn resolve(self, direction: FlexDirection) -> Self {
+        match (self, direction.is_reversed()) {
+            (JustifyContent::Start, true) => JustifyContent::End,
+            (JustifyContent::End, true) => JustifyContent::Start,
+            (justify, _) => justify,
+        }
+    }
+}
+
+/// The offset of the first child and the extra space added after every child.
+pub fn justify(justify: JustifyContent, free: usize, count: usize) -> (f32, f32) {
+    let free = free as f32;
+    let n = count as f32;
+    match justify {
+        JustifyContent::Start => (0.0, 0.0),
+        JustifyContent::Center => (free / 2.0, 0.0),
+        JustifyContent::End => (free, 0.0),
+        JustifyContent::SpaceBetween if count > 1 => (0.0, free / (n - 1.0)),
+        JustifyContent::SpaceBetween => (0.0, 0.0),
+        JustifyContent::SpaceAround if count > 0 => (free / n / 2.0, free / n),
+        JustifyContent::SpaceAround => (free / 2.0, 0.0),
+        JustifyContent::SpaceEvenly => (free / (n + 1.0), free / (n + 1.0)),
+    }
+}
+
+/// Offset of a child on the cross axis. `shift` is only used for baseline alignment.
+pub fn align_cross(align: AlignItems, available: usize, size: usize, shift: usize) -> usize {
+    match align {
+        AlignItems::Start | AlignItems::Stretch => 0,
+        AlignItems::Center => available.saturating_sub(size) / 2,
+        AlignItems::End => available.saturating_sub(size),
+        AlignItems::Baseline => shift,
+    }
+}
+
+/// (main, cross) lengths of the area.
+#[inline]
+pub const fn main_cross(area: Rect, horizontal: bool) -> (usize, usize) {
+    if horizontal {
+        (area.width, area.height)
+    } else {
+        (area.height, area.width)
+    }
+}
+
+/// Resize a widget with a percentage width or height to the size of it's parent.
+pub fn resolve_relative(item: FlexItem, area: &mut Rect, width: Option<usize>, height: Option<usize>) {
+    let percent = |length: usize, p: f32| (length as f32 * p / 100.0).round() as usize;
+    if let (Some(p), Some(width)) = (item.relative_width, width) {
+        area.width = percent(width, p);
+        item.constrain(area, true);
+    }
+    if let (Some(p), Some(height)) = (item.relative_height, height) {
+        area.height = percent(height, p);
+        item.constrain(area, false);
+    }
+}
+
+/// Resolve a percentage basis against the main size of the container, without a fixed size it's ignored.
+pub fn resolve_basis(item: &mut FlexItem, main: Option<usize>) {
+    if let (Some(p), Some(main)) = (item.relative_basis, main) {
+        item.basis = Some(Unit::Percentage(p).resolve(main, 0));
+    }
+}
+
+#[inline]
+pub fn set_main(area: &mut Rect, horizontal: bool, length: usize) {
+    if horizontal {
+        area.width = length;
+    } else {
+        area.height = length;
+    }
+}
+
+/// A row or column of a wrapping container.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlexLine {
+    /// Range of widgets in the line.
+    pub start: usize,
+    pub end: usize,
+    /// The largest child on the cross axis, including margins.
+    pub cross: usize,
+}
+
+/// Start a new line whenever the next child would go past `available`.
+pub fn break_lines(widgets: &[TypelessWidget], horizontal: bool, gap: usize, available: usize) -> Vec<FlexLine> {
+    let mut lines: Vec<FlexLine> = Vec::new();
+    //`None` until the line has a child that takes up space.
+    let mut main: Option<usize> = None;
+
+    for (i, widget) in widgets.iter().enumerate() {
+        //Absolute widgets are placed separately, they just need to be inside a line.
+        if widget.item.is_absolute() {
+            match lines.last_mut() {
+                Some(line) => line.end = i + 1,
+                None => lines.push(FlexLine {
+                    start: i,
+                    end: i + 1,
+                    cross: 0,
+                }),
+            }
+            continue;
+        }
+
+        let (main_margin, cross_margin) = widget.item.margin.total(horizontal);
+        let size = widget.item.hypothetical(widget.area, horizontal) + main_margin;
+        let cross = main_cross(widget.area, horizontal).1 + cross_margin;
+
+        match lines.last_mut() {
+            Some(line) if main.is_none_or(|main| main + gap + size <= available) => {
+                line.end = i + 1;
+                line.cross = line.cross.max(cross);
+                main = Some(main.map_or(size, |main| main + gap + size));
+            }
+            _ => {
+                lines.push(FlexLine {
+                    start: i,
+                    end: i + 1,
+                    cross,
+                });
+                main = Some(size);
+            }
+        }
+    }
+
+    lines
+}
+
+/// Grow or shrink the widgets so they fill `available`, including gaps and margins.
+pub fn resize_items(widgets: &mut [TypelessWidget], horizontal: bool, gap: usize, available: usize) {
+    let mut widgets: Vec<&mut TypelessWidget> = widgets.iter_mut().filter(|w| !w.item.is_absolute()).collect();
+    let gaps = gap * widgets.len().saturating_sub(1);
+    let margins: usize = widgets.iter().map(|w| w.item.margin.total(horizontal).0).sum();
+    let bases: Vec<usize> = widgets
+        .iter()
+        .map(|w| w.item.hypothetical(w.area, horizontal))
+        .collect();
+    let items: Vec<FlexItem> = widgets.iter().map(|w| w.item).collect();
+    let sizes = flex_sizes(&bases, &items, available.saturating_sub(gaps + margins), horizontal);
+    for (widget, size) in widgets.iter_mut().zip(sizes) {
+        set_main(&mut widget.area, horizontal, size);
+    }
+}
+
+/// Position the children of a container with it's top left corner at `(x, y)`.
+/// Children are placed inside the padding and are spaced out by their margins.
+pub fn draw_widgets(commands: &mut Vec<Command>, container: &mut Container, x: usize, y: usize) {
+    let horizontal = container.direction.is_horizontal();
+    let (_, cross_size) = main_cross(container.area, horizontal);
+    let (_, cross_padding) = container.padding.total(horizontal);
+    let (main_start, cross_start) = container.padding.start(horizontal);
+    let parent = Rect::new(x, y, container.area.width, container.area.height);
+
+    if let Some((enter, _)) = &container.handler {
         commands.push(Command {
-            area,
-            primative: widget.primative(),
+            area: parent,
+            primative: Primative::Handler(enter.clone()),
         });
+    }
 
-        //Add gap for every element except for the last.
-        if i != last_index {
-            match container.direction {
-                FlexDirection::LeftRight => *x_offset += container.gap,
-                FlexDirection::RightLeft => todo!(),
-                FlexDirection::TopBottom => *y_offset += container.gap,
-                FlexDirection::BottomTop => todo!(),
-            }
+    //Containers that don't wrap are a single line that fills the cross axis.
+    let lines = if container.lines.is_empty() {
+        vec![FlexLine {
+            start: 0,
+            end: container.widgets.len(),
+            cross: cross_size.saturating_sub(cross_padding),
+        }]
+    } else {
+        container.lines.clone()
+    };
+
+    let mut widgets = std::mem::take(&mut container.widgets);
+    if container.stacked {
+        draw_stack(commands, container, &mut widgets, x, y);
+    } else {
+        let mut line_offset = cross_start;
+        for line in lines {
+            let (line_x, line_y) = if horizontal {
+                (x + main_start, y + line_offset)
+            } else {
+                (x + line_offset, y + main_start)
+            };
+            draw_line(
+                commands,
+                container,
+                &mut widgets[line.start..line.end],
+                line_x,
+                line_y,
+                line.cross,
+            );
+            line_offset += line.cross + container.gap;
         }
+    }
+
+    //Absolute children are drawn on top, against the edges of the container.
+    for widget in widgets.iter_mut().filter(|w| w.item.is_absolute()) {
+        let mut area = widget.area;
+        area.x = x + container.padding.left;
+        area.y = y + container.padding.top;
+        widget.item.inset.place(&mut area, parent);
+        push_command(commands, widget, area);
+    }
+    container.widgets = widgets;
+
+    //Children are handled first, so keys bubble up from the focused widget.
+    if let Some((_, exit)) = &container.handler {
+        commands.push(Command {
+            area: parent,
+            primative: Primative::Handler(exit.clone()),
+        });
+    }
+}
 
-        match container.direction {
-            FlexDirection::LeftRight => *x_offset += area.width,
-            FlexDirection::RightLeft => todo!(),
-            FlexDirection::TopBottom => *y_offset += area.height,
-            FlexDirection::BottomTop => todo!(),
+/// Draw a widget at it's final area, nested containers lay their children out again.
+/// The handler follows the widget so it's run with the same area.
+pub fn push_command(commands: &mut Vec<Command>, widget: &mut TypelessWidget, area: Rect) {
+    match &mut widget.container {
+        Some(container) => {
+            container.area = area;
+            draw_widgets(commands, container, area.x, area.y);
         }
+        None => commands.push(Command {
+            area,
+            primative: widget.primative(),
+        }),
+    }
+
+    if let Some(handler) = &widget.handler {
+        commands.push(Command {
+            area,
+            primative: Primative::Handler(handler.clone()),
+        });
     }
 }
 
-/// Calculate the sizing of the flex container
-/// This is the total size of all containers and widgets.
-pub fn calculate_flex_sizing(direction: FlexDirection, gap: usize, container: &mut Container, area: &mut Rect) {
-    match direction {
-        FlexDirection::LeftRight => {
-            area.width += container.area.width + gap;
-            //Padding would have been overwritten here.
-            area.height = area.height.max(container.area.height)
+/// Every child of a `stack!` is placed inside the padding and aligned on both axes.
+fn draw_stack(commands: &mut Vec<Command>, container: &Container, widgets: &mut [TypelessWidget], x: usize, y: usize) {
+    let padding = container.padding;
+    let inner_width = container.area.width.saturating_sub(padding.left + padding.right);
+    let inner_height = container.area.height.saturating_sub(padding.top + padding.bottom);
+
+    for widget in widgets.iter_mut().filter(|w| !w.item.is_absolute()) {
+        let item = widget.item;
+        let margin = item.margin;
+        let horizontal = item.justify_self.unwrap_or(container.align_items);
+        let vertical = item.align_self.unwrap_or(container.align_items);
+        let width = inner_width.saturating_sub(margin.left + margin.right);
+        let height = inner_height.saturating_sub(margin.top + margin.bottom);
+
+        let mut area = widget.area;
+        if horizontal == AlignItems::Stretch {
+            area.width = item.clamp(width, true);
         }
-        FlexDirection::RightLeft => todo!(),
-        FlexDirection::TopBottom => {
-            area.width = area.width.max(container.area.width);
-            area.height += container.area.height + gap;
+        if vertical == AlignItems::Stretch {
+            area.height = item.clamp(height, false);
         }
-        FlexDirection::BottomTop => todo!(),
+        area.x = x + padding.left + margin.left + align_cross(horizontal, width, area.width, 0);
+        area.y = y + padding.top + margin.top + align_cross(vertical, height, area.height, 0);
+        item.inset.resolve(container.area).offset(&mut area);
+        push_command(commands, widget, area);
     }
 }
 
-pub fn add_gap(direction: FlexDirection, x_offset: &mut usize, y_offset: &mut usize, gap: usize) {
-    match direction {
-        FlexDirection::LeftRight => *x_offset += gap,
-        FlexDirection::RightLeft => todo!(),
-        FlexDirection::TopBottom => *y_offset += gap,
-        FlexDirection::BottomTop => todo!(),
+fn draw_line(
+    commands: &mut Vec<Command>,
+    container: &Container,
+    widgets: &mut [TypelessWidget],
+    x: usize,
+    y: usize,
+    cross_size: usize,
+) {
+    let direction = container.direction;
+    let horizontal = direction.is_horizontal();
+    let (main_size, _) = main_cross(container.area, horizontal);
+    let main_size = main_size.saturating_sub(container.padding.total(horizontal).0);
+    let mut widgets: Vec<&mut TypelessWidget> = widgets.iter_mut().filter(|w| !w.item.is_absolute()).collect();
+    //Reversed containers are placed backwards, so the first child ends up against the end.
+    if direction.is_reversed() {
+        widgets.reverse();
+    }
+    let count = widgets.len();
+    let gap = container.gap;
+    let content = widgets
+        .iter()
+        .map(|w| main_cross(w.area, horizontal).0 + w.item.margin.total(horizontal).0)
+        .sum::<usize>()
+        + gap * count.saturating_sub(1);
+    let justify_content = container.justify_content.resolve(direction);
+    let (start, between) = justify(justify_content, main_size.saturating_sub(content), count);
+    let baseline = widgets.iter().filter_map(|w| w.outer_baseline()).max().unwrap_or(0);
+
+    let mut offset = start;
+    for widget in widgets {
+        let mut area = widget.area.clone();
+        let margin = widget.item.margin;
+        let (main, cross) = main_cross(area, horizontal);
+        let (main_margin, cross_margin) = margin.total(horizontal);
+        let (main_before, cross_before) = margin.start(horizontal);
+        let shift = if horizontal {
+            baseline - widget.outer_baseline().unwrap_or(baseline)
+        } else {
+            0
+        };
+        let align = widget.item.align_self.unwrap_or(container.align_items);
+        let cross_offset = cross_before + align_cross(align, cross_size, cross + cross_margin, shift);
+        let main_offset = main_before + offset.round() as usize;
+        let stretched = widget.item.clamp(cross_size.saturating_sub(cross_margin), !horizontal);
+
+        if horizontal {
+            area.x = x + main_offset;
+            area.y = y + cross_offset;
+            if align == AlignItems::Stretch {
+                area.height = stretched;
+            }
+        } else {
+            area.x = x + cross_offset;
+            area.y = y + main_offset;
+            if align == AlignItems::Stretch {
+                area.width = stretched;
+            }
+        }
+
+        widget.item.inset.resolve(container.area).offset(&mut area);
+
+        push_command(commands, widget, area);
+
+        offset += (main + main_margin + gap) as f32 + between;
     }
 }
 
 #[macro_export]
 macro_rules! flex {
-    //Assume eveything being pass in is a container.
+    //Bare widgets are wrapped in a container of their own.
     ($($container:expr),* $(,)?) => {{
-        let f = |direction: $crate::FlexDirection, padding: $crate::Padding, gap: usize| {
-            //Start at the left padding and move right for every widget.
-            let mut x_offset = padding.left;
-            //Start at the top padding and move down for every widget.
-            let mut y_offset = padding.top;
-            let mut area = Rect::default();
-            let mut commands = Vec::new();
-            let count = $crate::count_expr!($($container),*);
-
+        let f = || {
+            let mut widgets = Vec::new();
             $(
-                //TODO: There is an error here if a widget is passed in instead of a container.
-                //This error message should be clearer.
-                let mut container = $container.build();
-                $crate::calculate_flex_sizing(direction, gap, &mut container, &mut area);
-                $crate::draw_widgets(&mut commands, direction, &mut container,  &mut x_offset, &mut y_offset, padding, gap);
-                $crate::add_gap(direction, &mut x_offset, &mut y_offset, gap);
+                $crate::Child::push_into(&mut $container, &mut widgets);
             )*
-
-            //Take away the extra gap added.
-            match direction {
-                FlexDirection::LeftRight => area.width -= gap,
-                FlexDirection::RightLeft => todo!(),
-                FlexDirection::TopBottom => area.height -= gap,
-                FlexDirection::BottomTop => todo!(),
-            }
-
-            area.width += padding.left + padding.right;
-            area.height += padding.top + padding.bottom;
-
-            $crate::Flex { commands, area }
+            widgets.into_iter().map($crate::Container::from).collect::<Vec<_>>()
         };
 
         $crate::DeferFlex {
@@ -194,7 +637,12 @@ macro_rules! flex {
             direction: $crate::FlexDirection::LeftRight,
             padding: $crate::Padding::default(),
             gap: 0,
+            align_items: $crate::AlignItems::Start,
+            justify_content: $crate::JustifyContent::Start,
+            width: None,
+            height: None,
             bg: None,
+            nested: false,
         }
     }}
 }
@@ -207,21 +655,48 @@ pub struct Flex {
 
 //Maybe group into one struct????
 //Could also convert into widget to simplify calling code.
-pub struct DeferFlex<F: FnMut(FlexDirection, Padding, usize) -> Flex> {
+pub struct DeferFlex<F: FnMut() -> Vec<Container>> {
     pub f: F,
     pub direction: FlexDirection,
     pub padding: Padding,
     pub gap: usize,
+    pub align_items: AlignItems,
+    pub justify_content: JustifyContent,
+    /// Fixed size of the flex, not including padding.
+    /// The content size is used when this is `None`.
+    pub width: Option<usize>,
+    pub height: Option<usize>,
     pub bg: Option<Color>,
+    /// Set when the flex is inside of another layout, which draws it instead.
+    pub nested: bool,
 }
 
-impl<F: FnMut(FlexDirection, Padding, usize) -> Flex> Drop for DeferFlex<F> {
+impl<F: FnMut() -> Vec<Container>> Drop for DeferFlex<F> {
     fn drop(&mut self) {
-        self.draw();
+        if !self.nested {
+            self.draw();
+        }
     }
 }
 
-impl<F: FnMut(FlexDirection, Padding, usize) -> Flex> DeferFlex<F> {
+impl<F: FnMut() -> Vec<Container>> Child for DeferFlex<F> {
+    fn push_into(&mut self, widgets: &mut Vec<TypelessWidget>) {
+        self.nested = true;
+        let mut flex = self.build();
+        if let Some(bg) = self.bg {
+            flex.commands.insert(
+                0,
+                Command {
+                    area: flex.area,
+                    primative: Primative::Ellipse(0, bg),
+                },
+            );
+        }
+        widgets.push(Container::from(flex).into());
+    }
+}
+
+impl<F: FnMut() -> Vec<Container>> DeferFlex<F> {
     pub fn draw(&mut self) {
         let mut flex = self.build();
 
@@ -263,16 +738,154 @@ impl<F: FnMut(FlexDirection, Padding, usize) -> Flex> DeferFlex<F> {
         self.direction = direction;
         self
     }
+    /// Place the first container last, `RightLeft` or `BottomTop`.
+    pub fn reverse(mut self) -> Self {
+        self.direction = self.direction.reverse();
+        self
+    }
+    pub fn align_items(mut self, align_items: AlignItems) -> Self {
+        self.align_items = align_items;
+        self
+    }
+    /// Needs a fixed size on the main axis to have any free space to share.
+    pub fn justify_content(mut self, justify_content: JustifyContent) -> Self {
+        self.justify_content = justify_content;
+        self
+    }
+    pub fn w(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+    pub fn h(mut self, height: usize) -> Self {
+        self.height = Some(height);
+        self
+    }
     pub fn bg(mut self, color: Color) -> Self {
         self.bg = Some(color);
         self
     }
 }
 
-impl<F: FnMut(FlexDirection, Padding, usize) -> Flex> Defer for DeferFlex<F> {
+impl<F: FnMut() -> Vec<Container>> Defer for DeferFlex<F> {
     type T = Flex;
     fn build(&mut self) -> Self::T {
-        (self.f)(self.direction, self.padding, self.gap)
+        let mut containers = (self.f)();
+        if self.direction.is_reversed() {
+            containers.reverse();
+        }
+        let (absolute, mut containers): (Vec<Container>, Vec<Container>) =
+            containers.into_iter().partition(|c| c.item.is_absolute());
+
+        let horizontal = self.direction.is_horizontal();
+        let count = containers.len();
+        let gaps = self.gap * count.saturating_sub(1);
+        let (fixed_main, fixed_cross) = if horizontal {
+            (self.width, self.height)
+        } else {
+            (self.height, self.width)
+        };
+
+        let margins: usize = containers.iter().map(|c| c.item.margin.total(horizontal).0).sum();
+
+        for container in &mut containers {
+            resolve_relative(container.item, &mut container.area, self.width, self.height);
+            resolve_basis(&mut container.item, fixed_main);
+        }
+
+        if let Some(main_size) = fixed_main {
+            let bases: Vec<usize> = containers
+                .iter()
+                .map(|c| c.item.hypothetical(c.area, horizontal))
+                .collect();
+            let items: Vec<FlexItem> = containers.iter().map(|c| c.item).collect();
+            let sizes = flex_sizes(&bases, &items, main_size.saturating_sub(gaps + margins), horizontal);
+            for (container, size) in containers.iter_mut().zip(sizes) {
+                set_main(&mut container.area, horizontal, size);
+            }
+        }
+
+        let content_main = containers
+            .iter()
+            .map(|c| main_cross(c.area, horizontal).0)
+            .sum::<usize>()
+            + gaps
+            + margins;
+        let content_cross = containers
+            .iter()
+            .map(|c| main_cross(c.area, horizontal).1 + c.item.margin.total(horizontal).1)
+            .max()
+            .unwrap_or(0);
+
+        let main_size = fixed_main.unwrap_or(content_main);
+        let cross_size = fixed_cross.unwrap_or(content_cross);
+        let justify_content = self.justify_content.resolve(self.direction);
+        let (start, between) = justify(justify_content, main_size.saturating_sub(content_main), count);
+        let baseline = containers.iter().map(|c| c.baseline).max().unwrap_or(0);
+
+        let (main_padding, cross_padding) = self.padding.start(horizontal);
+        let inner = if horizontal {
+            Rect::new(0, 0, main_size, cross_size)
+        } else {
+            Rect::new(0, 0, cross_size, main_size)
+        };
+
+        let mut commands = Vec::new();
+        let mut offset = start;
+        for mut container in containers {
+            let margin = container.item.margin;
+            let (main, cross) = main_cross(container.area, horizontal);
+            let (main_margin, cross_margin) = margin.total(horizontal);
+            let (main_before, cross_before) = margin.start(horizontal);
+            let shift = if horizontal { baseline - container.baseline } else { 0 };
+            let cross_offset = align_cross(self.align_items, cross_size, cross + cross_margin, shift);
+            if self.align_items == AlignItems::Stretch {
+                let stretched = container
+                    .item
+                    .clamp(cross_size.saturating_sub(cross_margin), !horizontal);
+                if horizontal {
+                    container.area.height = stretched;
+                } else {
+                    container.area.width = stretched;
+                }
+            }
+
+            let main_offset = main_padding + main_before + offset.round() as usize;
+            let cross_offset = cross_padding + cross_before + cross_offset;
+            let (x, y) = if horizontal {
+                (main_offset, cross_offset)
+            } else {
+                (cross_offset, main_offset)
+            };
+            let mut area = Rect::new(x, y, 0, 0);
+            container.item.inset.resolve(inner).offset(&mut area);
+
+            draw_widgets(&mut commands, &mut container, area.x, area.y);
+            offset += (main + main_margin + self.gap) as f32 + between;
+        }
+
+        let (width, height) = if horizontal {
+            (main_size.max(content_main), cross_size)
+        } else {
+            (cross_size, main_size.max(content_main))
+        };
+
+        let area = Rect::new(
+            0,
+            0,
+            width + self.padding.left + self.padding.right,
+            height + self.padding.top + self.padding.bottom,
+        );
+
+        for mut container in absolute {
+            let mut position = container.area;
+            position.x = self.padding.left;
+            position.y = self.padding.top;
+            container.item.inset.place(&mut position, area);
+            container.area = position;
+            draw_widgets(&mut commands, &mut container, position.x, position.y);
+        }
+
+        Flex { commands, area }
     }
 }
 
@@ -306,50 +919,40 @@ impl<F: FnMut(FlexDirection, Padding, usize) -> Flex> Defer for DeferFlex<F> {
 //This is because widgets need to be able to hold multiple other widgets.
 //We also have the Widget.is_container() function.
 
-pub fn calculate_h<T: Widget>(w: &T, width: &mut usize, height: &mut usize) {
+/// Absolute widgets are skipped since they don't take up any space, `flow` counts the rest.
+pub fn calculate_h<T: Widget>(w: &T, width: &mut usize, height: &mut usize, flow: &mut usize) {
     let area = w.area();
-    *height = area.height.max(*height);
-    *width += area.width;
+    let item = w.item();
+    if item.is_absolute() {
+        return;
+    }
+    *flow += 1;
+    *height = (area.height + item.margin.top + item.margin.bottom).max(*height);
+    *width += item.hypothetical(area, true) + item.margin.left + item.margin.right;
 }
 
 //Size the elements first and then set the positions later.
 #[macro_export]
 macro_rules! h {
     ($($widget:expr),* $(,)?) => {{
-        //TODO: Padding is unused here.
         let f = |padding: Padding, gap: usize| {
-            let count = $crate::count_expr!($($widget),*);
-            let total_gap = (count as i32 - 1) as usize * gap;
-            let mut width = total_gap;
-            let mut height = 0;
             let mut widgets = Vec::new();
-
             $(
-                let w = &mut $widget;
-
-                //This forces the container callback to be run.
-                //TODO: Improve this
-                let _ = unsafe { w.as_slice() };
-                let is_container = unsafe { w.is_container() };
-                if is_container {
-                    $crate::calculate_h(w, &mut width, &mut height);
-                }
-
-                for child in unsafe { w.as_slice() } {
-                    let area = child.area();
-                    if !is_container {
-                        height = area.height.max(height);
-                        width += area.width;
-                    }
-
-                    //Type is stripped from Click<T> here so calls
-                    //to widget.try_click() will always fail.
-                    widgets.push(TypelessWidget{ area, primative: child.primative()})
-                }
+                $crate::Child::push_into(&mut $widget, &mut widgets);
             )*
 
+            let mut width = 0;
+            let mut height = 0;
+            let mut flow = 0;
+            for widget in &widgets {
+                $crate::calculate_h(widget, &mut width, &mut height, &mut flow);
+            }
+
             //If there is only one element the gap is not important.
-            Container { direction: $crate::FlexDirection::LeftRight, widgets, area: Rect::new(0, 0, width, height), gap: if count > 1 { gap } else { 0 } }
+            let gap = if flow > 1 { gap } else { 0 };
+            width += gap * flow.saturating_sub(1);
+            let area = Rect::new(0, 0, width + padding.left + padding.right, height + padding.top + padding.bottom);
+            Container { direction: $crate::FlexDirection::LeftRight, widgets, area, padding, gap, ..Default::default() }
         };
 
         //Defer the creation of the container so that the builder pattern
@@ -358,50 +961,52 @@ macro_rules! h {
             f,
             padding: Padding::default(),
             gap: 0,
+            align_items: AlignItems::Start,
+            justify_content: JustifyContent::Start,
+            reverse: false,
+            wrap: false,
+            width: None,
+            height: None,
+            item: FlexItem::new(),
             container: Container::default(),
+            handler: None,
         }
     }};
 }
 
-pub fn calculate_v<T: Widget>(w: &T, width: &mut usize, height: &mut usize) {
+pub fn calculate_v<T: Widget>(w: &T, width: &mut usize, height: &mut usize, flow: &mut usize) {
     let area = w.area();
-    *width = area.width.max(*width);
-    *height += area.height;
+    let item = w.item();
+    if item.is_absolute() {
+        return;
+    }
+    *flow += 1;
+    *width = (area.width + item.margin.left + item.margin.right).max(*width);
+    *height += item.hypothetical(area, false) + item.margin.top + item.margin.bottom;
 }
 
 //There might be some way to reduce code-reuse here, but it's kind of necessary to avoid massive unintended match statements.
 #[macro_export]
 macro_rules! v {
     ($($widget:expr),* $(,)?) => {{
-        //TODO: Padding is unused here.
         let f = |padding: Padding, gap: usize| {
-            let count = $crate::count_expr!($($widget),*);
-            let total_gap = (count as i32 - 1) as usize * gap;
-            let mut width = 0;
-            let mut height = total_gap;
             let mut widgets = Vec::new();
-
             $(
-                let w = &mut $widget;
-
-                //This forces the container callback to be run.
-                //TODO: Improve this
-                let _ = unsafe { w.as_slice() };
-                let is_container = unsafe { w.is_container() };
-                if is_container {
-                    $crate::calculate_v(w, &mut width, &mut height);
-                }
-
-                for child in unsafe { w.as_slice() } {
-                    if !is_container {
-                        $crate::calculate_v(child, &mut width, &mut height);
-                    }
-                    widgets.push(TypelessWidget{ area: child.area(), primative: child.primative()})
-                }
+                $crate::Child::push_into(&mut $widget, &mut widgets);
             )*
 
+            let mut width = 0;
+            let mut height = 0;
+            let mut flow = 0;
+            for widget in &widgets {
+                $crate::calculate_v(widget, &mut width, &mut height, &mut flow);
+            }
+
             //If there is only one element the gap is not important.
-            Container { direction: $crate::FlexDirection::TopBottom, widgets, area: Rect::new(0, 0, width, height), gap: if count > 1 { gap } else { 0 }}
+            let gap = if flow > 1 { gap } else { 0 };
+            height += gap * flow.saturating_sub(1);
+            let area = Rect::new(0, 0, width + padding.left + padding.right, height + padding.top + padding.bottom);
+            Container { direction: $crate::FlexDirection::TopBottom, widgets, area, padding, gap, ..Default::default() }
         };
 
         //Defer the creation of the container so that the builder pattern
@@ -410,17 +1015,100 @@ macro_rules! v {
             f,
             padding: Padding::default(),
             gap: 0,
+            align_items: AlignItems::Start,
+            justify_content: JustifyContent::Start,
+            reverse: false,
+            wrap: false,
+            width: None,
+            height: None,
+            item: FlexItem::new(),
             container: Container::default(),
+            handler: None,
         }
     }};
 }
 
+/// Absolute widgets are skipped, every other child adds to the size of the stack.
+pub fn calculate_stack<T: Widget>(w: &T, width: &mut usize, height: &mut usize) {
+    let area = w.area();
+    let item = w.item();
+    if item.is_absolute() {
+        return;
+    }
+    *width = (area.width + item.margin.left + item.margin.right).max(*width);
+    *height = (area.height + item.margin.top + item.margin.bottom).max(*height);
+}
+
+/// Layer every child in the same area, the first child is drawn at the bottom.
+/// The stack is the size of it's largest child and each child can be aligned with
+/// `justify_self` and `align_self`.
+///
+/// ```ignore
+/// stack!(image("icon.png"), rect().wh(8).justify_self(AlignItems::End))
+/// ```
+#[macro_export]
+macro_rules! stack {
+    ($($widget:expr),* $(,)?) => {{
+        let f = |padding: Padding, _gap: usize| {
+            let mut widgets = Vec::new();
+            $(
+                $crate::Child::push_into(&mut $widget, &mut widgets);
+            )*
+
+            let mut width = 0;
+            let mut height = 0;
+            for widget in &widgets {
+                $crate::calculate_stack(widget, &mut width, &mut height);
+            }
+
+            let area = Rect::new(0, 0, width + padding.left + padding.right, height + padding.top + padding.bottom);
+            Container { widgets, area, padding, stacked: true, ..Default::default() }
+        };
+
+        $crate::DeferContainer {
+            f,
+            padding: Padding::default(),
+            gap: 0,
+            align_items: AlignItems::Start,
+            justify_content: JustifyContent::Start,
+            reverse: false,
+            wrap: false,
+            width: None,
+            height: None,
+            item: FlexItem::new(),
+            container: Container::default(),
+            handler: None,
+        }
+    }};
+}
+
+/// Short for `stack!`.
+#[macro_export]
+macro_rules! z {
+    ($($widget:expr),* $(,)?) => {
+        $crate::stack!($($widget),*)
+    };
+}
+
 #[derive(Default, Debug)]
 pub struct Container {
     pub widgets: Vec<TypelessWidget>,
     pub direction: FlexDirection,
     pub area: Rect,
+    pub padding: Padding,
     pub gap: usize,
+    pub align_items: AlignItems,
+    pub justify_content: JustifyContent,
+    /// The largest baseline of all the children.
+    pub baseline: usize,
+    /// Only set when the container wraps.
+    pub lines: Vec<FlexLine>,
+    /// Children are layered on top of each other, see `stack!`.
+    pub stacked: bool,
+    /// Used when the container is inside a `flex!`.
+    pub item: FlexItem,
+    /// Run before and after the children, with the area of the container, see `key_handler`.
+    pub handler: Option<(Handler, Handler)>,
 }
 
 impl Widget for Container {
@@ -443,6 +1131,127 @@ impl Widget for Container {
 pub struct TypelessWidget {
     pub area: Rect,
     pub primative: Primative,
+    pub baseline: Option<usize>,
+    pub item: FlexItem,
+    /// Nested containers are kept together and laid out again once their area is known.
+    pub container: Option<Box<Container>>,
+    pub handler: Option<Handler>,
+}
+
+impl TypelessWidget {
+    pub fn new<T: Widget>(widget: &T) -> Self {
+        Self {
+            area: widget.area(),
+            primative: widget.primative(),
+            baseline: widget.baseline(),
+            item: widget.item(),
+            container: None,
+            handler: widget.handler(),
+        }
+    }
+    /// The baseline measured from the top of the margin.
+    pub fn outer_baseline(&self) -> Option<usize> {
+        self.baseline.map(|b| b + self.item.margin.top)
+    }
+}
+
+impl From<Container> for TypelessWidget {
+    fn from(container: Container) -> Self {
+        Self {
+            area: container.area,
+            //Containers are drawn from their children.
+            primative: Primative::Custom(|_, _| {}),
+            baseline: Some(container.baseline).filter(|b| *b != 0),
+            item: container.item,
+            container: Some(Box::new(container)),
+            handler: None,
+        }
+    }
+}
+
+/// Bare widgets are wrapped in a container of their own that they fill.
+impl From<TypelessWidget> for Container {
+    fn from(mut widget: TypelessWidget) -> Self {
+        if let Some(container) = widget.container.take() {
+            return *container;
+        }
+
+        //The container takes over the flex properties, only the size constraints are kept.
+        let item = widget.item;
+        widget.item = FlexItem {
+            min_width: item.min_width,
+            max_width: item.max_width,
+            min_height: item.min_height,
+            max_height: item.max_height,
+            aspect_ratio: item.aspect_ratio,
+            ..FlexItem::new()
+        };
+        Container {
+            area: widget.area,
+            baseline: widget.baseline.unwrap_or(0),
+            align_items: AlignItems::Stretch,
+            stacked: true,
+            item,
+            widgets: vec![widget],
+            ..Default::default()
+        }
+    }
+}
+
+/// The commands of a `flex!` or `grid!` keep their positions inside of the container.
+impl From<Flex> for Container {
+    fn from(flex: Flex) -> Self {
+        let widgets = flex
+            .commands
+            .into_iter()
+            .map(|cmd| TypelessWidget {
+                area: cmd.area,
+                primative: cmd.primative,
+                baseline: None,
+                item: FlexItem {
+                    position: Position::Absolute,
+                    inset: Inset {
+                        left: Some(cmd.area.x.saturating_sub(flex.area.x)),
+                        top: Some(cmd.area.y.saturating_sub(flex.area.y)),
+                        ..Inset::new()
+                    },
+                    ..FlexItem::new()
+                },
+                container: None,
+                handler: None,
+            })
+            .collect();
+
+        Container {
+            widgets,
+            area: Rect::new(0, 0, flex.area.width, flex.area.height),
+            stacked: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Anything that can be placed inside of `h!`, `v!`, `stack!`, `grid!` or `flex!`.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be placed inside of a layout",
+    label = "expected a widget, a container or a `flex!`",
+    note = "widgets implement `Widget`, containers are created with `h!`, `v!` and `stack!`"
+)]
+pub trait Child {
+    fn push_into(&mut self, widgets: &mut Vec<TypelessWidget>);
+}
+
+impl<T: Widget> Child for T
+where
+    T::Layout: Widget,
+{
+    fn push_into(&mut self, widgets: &mut Vec<TypelessWidget>) {
+        match self.build_container() {
+            Some(container) => widgets.push(container.into()),
+            //The type is stripped here, the behaviour is kept in a handler.
+            None => widgets.extend(unsafe { self.as_slice() }.iter().map(TypelessWidget::new)),
+        }
+    }
 }
 
 impl Widget for TypelessWidget {
@@ -460,13 +1269,63 @@ impl Widget for TypelessWidget {
     fn area_mut(&mut self) -> Option<&mut Rect> {
         Some(&mut self.area)
     }
+
+    fn baseline(&self) -> Option<usize> {
+        self.baseline
+    }
+
+    fn item(&self) -> FlexItem {
+        self.item
+    }
+
+    fn item_mut(&mut self) -> Option<&mut FlexItem> {
+        Some(&mut self.item)
+    }
+
+    fn build_container(&mut self) -> Option<Container> {
+        self.container.take().map(|container| *container)
+    }
+}
+
+/// Move every child with a baseline down so they all line up with the lowest one.
+/// Only rows can be aligned, columns are left untouched.
+pub fn align_baselines(container: &mut Container) {
+    if !container.direction.is_horizontal() {
+        return;
+    }
+
+    let baseline = container
+        .widgets
+        .iter()
+        .filter_map(|w| w.outer_baseline())
+        .max()
+        .unwrap_or(0);
+    let mut height = 0;
+
+    for widget in &container.widgets {
+        let shift = baseline - widget.outer_baseline().unwrap_or(baseline);
+        let margin = widget.item.margin;
+        height = height.max(shift + widget.area.height + margin.top + margin.bottom);
+    }
+
+    container.baseline = baseline;
+    container.area.height = height + container.padding.top + container.padding.bottom;
 }
 
 pub struct DeferContainer<F> {
     pub f: F,
     pub padding: Padding,
     pub gap: usize,
+    pub align_items: AlignItems,
+    pub justify_content: JustifyContent,
+    pub reverse: bool,
+    pub wrap: bool,
+    /// Set with `width()` and `height()`, the content size is used otherwise.
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub item: FlexItem,
     pub container: Container,
+    pub handler: Option<(Handler, Handler)>,
 }
 
 impl<F> DeferContainer<F> {
@@ -478,6 +1337,51 @@ impl<F> DeferContainer<F> {
         self.padding = Padding::new(padding, padding, padding, padding);
         self
     }
+    pub fn left_pad(mut self, padding: usize) -> Self {
+        self.padding.left = padding;
+        self
+    }
+    pub fn right_pad(mut self, padding: usize) -> Self {
+        self.padding.right = padding;
+        self
+    }
+    pub fn top_pad(mut self, padding: usize) -> Self {
+        self.padding.top = padding;
+        self
+    }
+    pub fn bottom_pad(mut self, padding: usize) -> Self {
+        self.padding.bottom = padding;
+        self
+    }
+    /// Line up the first baseline of every text widget in a `h!` row.
+    pub fn align_baseline(self) -> Self {
+        self.align_items(AlignItems::Baseline)
+    }
+    pub fn align_items(mut self, align_items: AlignItems) -> Self {
+        self.align_items = align_items;
+        self
+    }
+    /// Needs a fixed size on the main axis to have any free space to share.
+    pub fn justify_content(mut self, justify_content: JustifyContent) -> Self {
+        self.justify_content = justify_content;
+        self
+    }
+    /// Lay the children out right to left in a `h!` or bottom to top in a `v!`.
+    pub fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+    /// Move children onto a new row or column when they don't fit.
+    /// The width or height of the container is used, or the window when there isn't one.
+    pub fn wrap(mut self) -> Self {
+        self.wrap = true;
+        self
+    }
+    /// Keys the focused widget inside of the container didn't handle, return `true` when the key is handled.
+    pub fn on_key(mut self, f: impl FnMut(&Key, &Modifiers) -> bool + 'static) -> Self {
+        self.handler = Some(key_handler(f));
+        self
+    }
 }
 
 impl<F> Widget for DeferContainer<F>
@@ -500,16 +1404,45 @@ where
         self.container.area
     }
 
+    //The area isn't known until the container is built.
     fn area_mut(&mut self) -> Option<&mut Rect> {
-        unimplemented!()
-        // self.container = self.build();
-        // Some(&mut self.container.area)
+        None
     }
 
     unsafe fn as_slice(&mut self) -> &[Self::Layout] {
         self.container = self.build();
         &self.container.widgets
     }
+
+    fn build_container(&mut self) -> Option<Container> {
+        Some(self.build())
+    }
+
+    fn item(&self) -> FlexItem {
+        self.item
+    }
+
+    fn item_mut(&mut self) -> Option<&mut FlexItem> {
+        Some(&mut self.item)
+    }
+
+    fn width<U: Into<Unit>>(mut self, length: U) -> Self {
+        let length = length.into();
+        self.width = Some(length.resolve(viewport().width, self.em_size()));
+        if let Unit::Percentage(p) = length {
+            self.item.relative_width = Some(p);
+        }
+        self
+    }
+
+    fn height<U: Into<Unit>>(mut self, length: U) -> Self {
+        let length = length.into();
+        self.height = Some(length.resolve(viewport().height, self.em_size()));
+        if let Unit::Percentage(p) = length {
+            self.item.relative_height = Some(p);
+        }
+        self
+    }
 }
 
 impl<F> Defer for DeferContainer<F>
@@ -518,7 +1451,100 @@ where
 {
     type T = Container;
     fn build(&mut self) -> Self::T {
-        (self.f)(self.padding, self.gap)
+        let mut container = (self.f)(self.padding, self.gap);
+        let item = self.item;
+        self.width = self.width.map(|width| item.clamp(width, true));
+        self.height = self.height.map(|height| item.clamp(height, false));
+        if self.reverse {
+            container.direction = container.direction.reverse();
+        }
+        container.align_items = self.align_items;
+        container.item = self.item;
+        container.handler = self.handler.clone();
+        container.justify_content = self.justify_content;
+        container.baseline = container
+            .widgets
+            .iter()
+            .filter_map(|w| w.outer_baseline())
+            .max()
+            .unwrap_or(0);
+        if self.align_items == AlignItems::Baseline && !container.stacked {
+            align_baselines(&mut container);
+        }
+        if let Some(width) = self.width {
+            container.area.width = width;
+        }
+        if let Some(height) = self.height {
+            container.area.height = height;
+        }
+
+        let horizontal = container.direction.is_horizontal();
+        let (fixed_main, fixed_cross) = if horizontal {
+            (self.width, self.height)
+        } else {
+            (self.height, self.width)
+        };
+        let (main_padding, cross_padding) = container.padding.total(horizontal);
+
+        //Percentages can only be resolved against a container with a fixed size.
+        let inner_width = self
+            .width
+            .map(|w| w.saturating_sub(container.padding.left + container.padding.right));
+        let inner_height = self
+            .height
+            .map(|h| h.saturating_sub(container.padding.top + container.padding.bottom));
+        let inner_main = if horizontal { inner_width } else { inner_height };
+        for widget in &mut container.widgets {
+            resolve_relative(widget.item, &mut widget.area, inner_width, inner_height);
+            resolve_basis(&mut widget.item, inner_main);
+        }
+
+        if self.wrap && !container.stacked {
+            //Without a fixed size the children wrap at the edge of the window.
+            let available = fixed_main
+                .unwrap_or_else(|| main_cross(viewport(), horizontal).0)
+                .saturating_sub(main_padding);
+            let gap = container.gap;
+            container.lines = break_lines(&container.widgets, horizontal, gap, available);
+
+            let mut main = 0;
+            for line in &container.lines {
+                let widgets = &mut container.widgets[line.start..line.end];
+                if fixed_main.is_some() {
+                    resize_items(widgets, horizontal, gap, available);
+                }
+                let widgets: Vec<&TypelessWidget> = widgets.iter().filter(|w| !w.item.is_absolute()).collect();
+                let length = widgets
+                    .iter()
+                    .map(|w| main_cross(w.area, horizontal).0 + w.item.margin.total(horizontal).0)
+                    .sum::<usize>()
+                    + gap * widgets.len().saturating_sub(1);
+                main = main.max(length);
+            }
+
+            let lines = container.lines.len();
+            let cross = container.lines.iter().map(|l| l.cross).sum::<usize>() + gap * lines.saturating_sub(1);
+            let main = fixed_main.unwrap_or(main + main_padding);
+            let cross = fixed_cross.unwrap_or(cross + cross_padding);
+            container.area = if horizontal {
+                Rect::new(0, 0, main, cross)
+            } else {
+                Rect::new(0, 0, cross, main)
+            };
+        } else if let Some(main_size) = fixed_main.filter(|_| !container.stacked) {
+            //Children can only grow or shrink when the container has a fixed size.
+            resize_items(
+                &mut container.widgets,
+                horizontal,
+                container.gap,
+                main_size.saturating_sub(main_padding),
+            );
+        }
+
+        container.area.width = self.item.clamp(container.area.width, true);
+        container.area.height = self.item.clamp(container.area.height, false);
+
+        container
     }
 }
 
@@ -565,4 +1591,348 @@ mod tests {
         assert_eq!(f.area.width, 20 + 5 + 20 + 5 + 20);
         assert_eq!(f.area.height, 20 + 5 + 20);
     }
+
+    #[test]
+    fn reversed() {
+        let mut container = h!(rect().w(10).h(10), rect().w(20).h(10)).gap(5).reverse().build();
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 0, 0);
+
+        //The first child is drawn on the right.
+        assert_eq!(commands[0].area.width, 20);
+        assert_eq!(commands[0].area.x, 0);
+        assert_eq!(commands[1].area.x, 25);
+        assert_eq!(container.area.width, 35);
+
+        let flex = flex!(h!(rect().wh(10)), h!(rect().wh(20))).reverse().build();
+        assert_eq!(flex.commands[0].area.width, 20);
+        assert_eq!(flex.commands[1].area.x, 20);
+    }
+
+    #[test]
+    fn reversed_fixed_size() {
+        //Packed against the right edge, the first child is on the far right.
+        let mut container = h!(rect().wh(10), rect().w(20).h(10)).w(100).reverse().build();
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 0, 0);
+        assert_eq!((commands[0].area.x, commands[0].area.width), (70, 20));
+        assert_eq!((commands[1].area.x, commands[1].area.width), (90, 10));
+
+        //Packed against the bottom.
+        let mut container = v!(rect().wh(10), rect().w(10).h(20)).h(100).reverse().build();
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 0, 0);
+        assert_eq!((commands[0].area.y, commands[0].area.height), (70, 20));
+        assert_eq!((commands[1].area.y, commands[1].area.height), (90, 10));
+
+        let flex = flex!(h!(rect().wh(10)), h!(rect().wh(20))).w(100).reverse().build();
+        assert_eq!((flex.commands[0].area.x, flex.commands[0].area.width), (70, 20));
+        assert_eq!((flex.commands[1].area.x, flex.commands[1].area.width), (90, 10));
+    }
+
+    #[test]
+    fn reversed_justify() {
+        //The end of a reversed row is the left edge.
+        let mut container = h!(rect().wh(10), rect().w(20).h(10))
+            .w(100)
+            .reverse()
+            .justify_content(JustifyContent::End)
+            .build();
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 0, 0);
+        assert_eq!((commands[0].area.x, commands[0].area.width), (0, 20));
+        assert_eq!((commands[1].area.x, commands[1].area.width), (20, 10));
+
+        assert_eq!(
+            JustifyContent::Start.resolve(FlexDirection::BottomTop),
+            JustifyContent::End
+        );
+        assert_eq!(
+            JustifyContent::Center.resolve(FlexDirection::RightLeft),
+            JustifyContent::Center
+        );
+
+        let flex = flex!(h!(rect().wh(10)), h!(rect().wh(20)))
+            .w(100)
+            .reverse()
+            .justify_content(JustifyContent::End)
+            .build();
+        assert_eq!((flex.commands[0].area.x, flex.commands[0].area.width), (0, 20));
+        assert_eq!((flex.commands[1].area.x, flex.commands[1].area.width), (20, 10));
+    }
+
+    #[test]
+    fn alignment() {
+        let mut container = h!(rect().w(10).h(10), rect().w(10).h(30))
+            .w(100)
+            .align_items(AlignItems::Center)
+            .justify_content(JustifyContent::SpaceBetween)
+            .build();
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 0, 0);
+
+        assert_eq!(container.area.width, 100);
+        assert_eq!(commands[0].area.y, 10);
+        assert_eq!(commands[1].area.y, 0);
+        assert_eq!(commands[1].area.x, 90);
+
+        assert_eq!(justify(JustifyContent::SpaceAround, 40, 2), (10.0, 20.0));
+        assert_eq!(justify(JustifyContent::SpaceEvenly, 30, 2), (10.0, 10.0));
+        assert_eq!(justify(JustifyContent::End, 30, 2), (30.0, 0.0));
+
+        let flex = flex!(h!(rect().wh(10)), h!(rect().wh(20)))
+            .w(100)
+            .justify_content(JustifyContent::End)
+            .align_items(AlignItems::End)
+            .build();
+        assert_eq!(flex.area.width, 100);
+        assert_eq!(flex.commands[0].area.x, 70);
+        assert_eq!(flex.commands[0].area.y, 10);
+    }
+
+    #[test]
+    fn grow_and_shrink() {
+        assert_eq!(
+            flex_sizes(&[10, 10], &[FlexItem::new(), FlexItem::new()], 20, true),
+            [10, 10]
+        );
+
+        //Only the second item grows.
+        let grow = FlexItem {
+            grow: 1.0,
+            ..FlexItem::new()
+        };
+        assert_eq!(flex_sizes(&[10, 10], &[FlexItem::new(), grow], 50, true), [10, 40]);
+
+        //Shrinking is weighted by the basis.
+        assert_eq!(
+            flex_sizes(&[10, 30], &[FlexItem::new(), FlexItem::new()], 20, true),
+            [5, 15]
+        );
+
+        //A sidebar with a fixed width and content that fills the rest.
+        let container = h!(rect().w(100).shrink(0.0), rect().w(0).grow(1.0)).w(400).build();
+        assert_eq!(container.widgets[0].area.width, 100);
+        assert_eq!(container.widgets[1].area.width, 300);
+
+        let container = v!(rect().h(10), rect().basis(20).grow(1.0)).gap(10).h(100).build();
+        assert_eq!(container.widgets[1].area.height, 80);
+
+        //Percentages are of the main axis, not the window width.
+        let container = v!(rect().w(10).basis(0.5), rect().wh(10)).h(400).build();
+        assert_eq!(container.widgets[0].area.height, 200);
+        let container = h!(rect().h(10).basis(0.25), rect().wh(10)).w(400).padding(50).build();
+        assert_eq!(container.widgets[0].area.width, 75);
+
+        let flex = flex!(v!(rect().wh(10)).basis(0.5), v!(rect().wh(10)))
+            .h(300)
+            .direction(FlexDirection::TopBottom)
+            .build();
+        assert_eq!(flex.commands[1].area.y, 150);
+    }
+
+    #[test]
+    fn padding_and_margin() {
+        let mut container = h!(rect().wh(10).margin(5), rect().wh(10)).padding(10).gap(2).build();
+        assert_eq!(container.area.width, 10 + 5 + 10 + 5 + 2 + 10 + 10);
+        assert_eq!(container.area.height, 10 + 5 + 10 + 5 + 10);
+
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 0, 0);
+        assert_eq!((commands[0].area.x, commands[0].area.y), (15, 15));
+        assert_eq!((commands[1].area.x, commands[1].area.y), (32, 10));
+
+        let container = v!(rect().wh(10).top_margin(4)).left_pad(3).build();
+        assert_eq!(container.area.width, 13);
+        assert_eq!(container.area.height, 14);
+    }
+
+    #[test]
+    fn wrapping() {
+        let mut container = h!(rect().w(40).h(10), rect().w(40).h(20), rect().w(40).h(10))
+            .gap(5)
+            .w(100)
+            .wrap()
+            .build();
+
+        assert_eq!(container.lines.len(), 2);
+        assert_eq!(container.lines[0].cross, 20);
+        assert_eq!(container.area.width, 100);
+        assert_eq!(container.area.height, 20 + 5 + 10);
+
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 0, 0);
+        assert_eq!((commands[1].area.x, commands[1].area.y), (45, 0));
+        assert_eq!((commands[2].area.x, commands[2].area.y), (0, 25));
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(Unit::Em(1.5).resolve(0, 20), 30);
+        assert_eq!(Unit::Percentage(25.0).resolve(200, 20), 50);
+        assert_eq!(Unit::from(0.5), Unit::Percentage(50.0));
+
+        //Half of the container, not including the padding.
+        let container = h!(rect().w(0.5).h(10), rect().w(2.0.em())).w(220).padding(10).build();
+        assert_eq!(container.widgets[0].area.width, 100);
+        assert_eq!(container.widgets[1].area.width, default_font_size() * 2);
+    }
+
+    #[test]
+    fn absolute() {
+        //The badge doesn't take up any space or gap.
+        let mut container = h!(rect().wh(50), rect().wh(50), rect().wh(10).absolute().right(5).top(5))
+            .gap(10)
+            .padding(10)
+            .build();
+        assert_eq!(container.area.width, 130);
+
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 0, 0);
+        assert_eq!((commands[1].area.x, commands[1].area.y), (70, 10));
+        assert_eq!((commands[2].area.x, commands[2].area.y), (115, 5));
+
+        //Opposite insets stretch the widget.
+        let mut area = Rect::new(0, 0, 10, 10);
+        let inset = Inset {
+            left: Some(10),
+            right: Some(20),
+            bottom: Some(0),
+            ..Default::default()
+        };
+        inset.place(&mut area, Rect::new(0, 0, 100, 100));
+        assert_eq!(area, Rect::new(10, 90, 70, 10));
+
+        //Relative widgets are moved after they're laid out.
+        let mut container = v!(rect().wh(20), rect().wh(20).relative().left(5)).build();
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 0, 0);
+        assert_eq!((commands[1].area.x, commands[1].area.y), (5, 20));
+
+        //Standalone widgets are placed with their final size.
+        let badge = rect().right(10).w(50);
+        let mut area = badge.area;
+        badge.item().inset.place(&mut area, Rect::new(0, 0, 1000, 1000));
+        assert_eq!((area.x, area.width), (940, 50));
+
+        //Percentages are relative to the container, not the window.
+        let mut container = h!(rect().wh(10).absolute().left(0.5).top(0.25)).wh(200).build();
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 0, 0);
+        assert_eq!((commands[0].area.x, commands[0].area.y), (100, 50));
+    }
+
+    #[test]
+    fn constraints() {
+        let item = FlexItem {
+            min_width: Some(120),
+            max_width: Some(200),
+            ..FlexItem::new()
+        };
+        assert_eq!(item.clamp(50, true), 120);
+        assert_eq!(item.clamp(500, true), 200);
+        assert_eq!(item.clamp(500, false), 500);
+
+        //The capped item gives it's share of the free space to the other.
+        let capped = FlexItem {
+            grow: 1.0,
+            max_width: Some(30),
+            ..FlexItem::new()
+        };
+        let grow = FlexItem {
+            grow: 1.0,
+            ..FlexItem::new()
+        };
+        assert_eq!(flex_sizes(&[10, 10], &[capped, grow], 100, true), [30, 70]);
+
+        let container = h!(rect().w(100).min_w(80), rect().w(100)).w(100).build();
+        assert_eq!(container.widgets[0].area.width, 80);
+        assert_eq!(container.widgets[1].area.width, 20);
+
+        let rect = rect().aspect_ratio(2.0).w(100);
+        assert_eq!(rect.area.height, 50);
+        let rect = rect.max_h(20);
+        assert_eq!((rect.area.width, rect.area.height), (100, 20));
+    }
+
+    #[test]
+    fn stack() {
+        let mut container = stack!(
+            rect().wh(40),
+            rect().wh(10).justify_self(AlignItems::End),
+            rect()
+                .w(20)
+                .h(10)
+                .align_self(AlignItems::Center)
+                .justify_self(AlignItems::Center)
+        )
+        .padding(5)
+        .build();
+        assert_eq!((container.area.width, container.area.height), (50, 50));
+
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 0, 0);
+        assert_eq!((commands[0].area.x, commands[0].area.y), (5, 5));
+        assert_eq!((commands[1].area.x, commands[1].area.y), (35, 5));
+        assert_eq!((commands[2].area.x, commands[2].area.y), (15, 20));
+    }
+
+    #[test]
+    fn nesting() {
+        //The row stays together inside the column.
+        let mut container = v!(h!(rect().wh(10), rect().wh(10)).gap(5), rect().wh(10)).build();
+        assert_eq!((container.area.width, container.area.height), (25, 20));
+
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 0, 0);
+        assert_eq!((commands[1].area.x, commands[1].area.y), (15, 0));
+        assert_eq!((commands[2].area.x, commands[2].area.y), (0, 10));
+
+        //Bare widgets and other roots can go straight into a flex.
+        let flex = flex!(
+            rect().wh(10),
+            v!(rect().wh(10), rect().wh(10)),
+            flex!(rect().wh(5)).padding(2)
+        )
+        .gap(5)
+        .build();
+        assert_eq!(flex.area.width, 10 + 5 + 10 + 5 + 9);
+        assert_eq!(flex.area.height, 20);
+        assert_eq!(flex.commands.len(), 4);
+        assert_eq!((flex.commands[2].area.x, flex.commands[2].area.y), (15, 10));
+        assert_eq!((flex.commands[3].area.x, flex.commands[3].area.y), (32, 2));
+    }
+
+    #[test]
+    fn handlers() {
+        //Click behaviour follows the widget to it's final area.
+        let mut container = v!(
+            rect().wh(10),
+            h!(rect().wh(10), Basic::default().wh(10).on_click(Left, |_| {})).gap(5)
+        )
+        .padding(2)
+        .build();
+
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 0, 0);
+        assert_eq!(commands.len(), 4);
+        assert!(matches!(commands[3].primative, Primative::Handler(_)));
+        assert_eq!(commands[3].area, commands[2].area);
+        assert_eq!((commands[3].area.x, commands[3].area.y), (17, 12));
+
+        //Widgets without any behaviour don't need one.
+        assert!(Basic::default().handler().is_none());
+
+        //Containers are handled after their children, which are marked as being inside of it first.
+        let mut container = v!(h!(rect().wh(10)).on_key(|_, _| false), rect().wh(10))
+            .padding(2)
+            .build();
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 0, 0);
+        assert_eq!(commands.len(), 4);
+        assert!(matches!(commands[0].primative, Primative::Handler(_)));
+        assert!(matches!(commands[2].primative, Primative::Handler(_)));
+        assert_eq!(commands[0].area, Rect::new(2, 2, 10, 10));
+        assert_eq!(commands[2].area, commands[0].area);
+    }
 }
@@ -0,0 +1,217 @@
+//! Keyboard focus, moved with Tab and Shift-Tab or by clicking on a focusable widget.
+//!
+//! ```rs
+//! v!(
+//!     Basic::default().on_key(|_, key, _| *key == Key::Enter),
+//!     Basic::default().focusable(),
+//! )
+//! .on_key(|key, _| *key == Key::Escape);
+//! ```
+//!
+//! Keys go to the focused widget first, anything it doesn't handle is passed to the containers it's drawn inside of.
+//! Focusable widgets are told apart by their `WidgetId`, the ones created in a loop should be given an `id`.
+//! The Tab order is the order they're drawn in.
+use crate::*;
+
+/// Space between a widget and the focus ring.
+pub const FOCUS_RING_GAP: usize = 2;
+
+#[derive(Debug)]
+pub struct FocusState {
+    /// Focusable widgets registered this frame.
+    pub widgets: Vec<HoverId>,
+    /// Focusable widgets registered last frame, in the order they were drawn.
+    pub previous: Vec<HoverId>,
+    pub focused: Option<HoverId>,
+    /// One for every container with a key handler being drawn, set once the focused widget is drawn inside of it.
+    pub ancestors: Vec<bool>,
+    /// The focus ring is only shown after the keyboard is used to move the focus.
+    pub visible: bool,
+    pub ring: Option<Rect>,
+    pub ring_color: Color,
+    /// Keys received this frame, (key, modifiers, chords recorded by `Shortcuts::record`, handled).
+    pub keys: Vec<(Key, Modifiers, usize, bool)>,
+}
+
+impl Default for FocusState {
+    fn default() -> Self {
+        Self {
+            widgets: Vec::new(),
+            previous: Vec::new(),
+            focused: None,
+            ancestors: Vec::new(),
+            visible: false,
+            ring: None,
+            ring_color: rgb(0, 120, 215),
+            keys: Vec::new(),
+        }
+    }
+}
+
+impl FocusState {
+    pub fn register(&mut self, id: HoverId) {
+        self.widgets.push(id);
+    }
+
+    pub fn focus(&mut self, id: HoverId) {
+        self.focused = Some(id);
+    }
+
+    pub fn blur(&mut self) {
+        self.focused = None;
+    }
+
+    /// Move to the next focusable widget, or the previous one when `reverse` is set.
+    pub fn next(&mut self, reverse: bool) {
+        let count = self.previous.len();
+        if count == 0 {
+            return;
+        }
+        let index = self.focused.and_then(|id| self.previous.iter().position(|p| *p == id));
+        let index = match (index, reverse) {
+            (None, false) => 0,
+            (None, true) => count - 1,
+            (Some(index), false) => (index + 1) % count,
+            (Some(index), true) => (index + count - 1) % count,
+        };
+        self.focused = Some(self.previous[index]);
+        self.visible = true;
+    }
+
+    /// Tab and Shift-Tab move the focus, every other key is kept for the focused widget.
+    pub fn record(&mut self, key: &Key, modifiers: &Modifiers, chords: usize) {
+        if *key == Key::Tab {
+            self.next(modifiers.shift);
+        } else {
+            self.keys.push((*key, *modifiers, chords, false));
+        }
+    }
+
+    /// There are keys that haven't been handled yet.
+    pub fn pending(&self) -> bool {
+        self.keys.iter().any(|(_, _, _, handled)| !handled)
+    }
+
+    /// Give every key that hasn't been handled to `f`, which returns `true` when it handles it.
+    pub fn handle(&mut self, mut f: impl FnMut(&Key, &Modifiers) -> bool) {
+        for (key, modifiers, _, handled) in &mut self.keys {
+            if !*handled && f(key, modifiers) {
+                *handled = true;
+            }
+        }
+    }
+
+    /// A container with a key handler is about to draw it's children.
+    pub fn enter(&mut self) {
+        self.ancestors.push(false);
+    }
+
+    /// The focused widget was drawn, every container that's still being drawn is around it.
+    pub fn drawn(&mut self) {
+        self.ancestors.fill(true);
+    }
+
+    /// The children of the container have been drawn, returns `true` when the focused widget was one of them.
+    pub fn exit(&mut self) -> bool {
+        self.ancestors.pop().unwrap_or(false)
+    }
+
+    /// Called once all of the widgets have been drawn.
+    pub fn next_frame(&mut self) {
+        self.previous = std::mem::take(&mut self.widgets);
+        if self.focused.is_some_and(|id| !self.previous.contains(&id)) {
+            self.focused = None;
+        }
+        self.keys.clear();
+        self.ancestors.clear();
+        self.ring = None;
+    }
+
+    /// Drawn around the focused widget, on top of everything else.
+    pub fn ring_command(&self) -> Option<Command> {
+        let ring = self.ring?;
+        Some(Command {
+            area: Rect::new(
+                ring.x.saturating_sub(FOCUS_RING_GAP),
+                ring.y.saturating_sub(FOCUS_RING_GAP),
+                ring.width + FOCUS_RING_GAP * 2,
+                ring.height + FOCUS_RING_GAP * 2,
+            ),
+            primative: Primative::RectangleOutline(self.ring_color),
+        })
+    }
+}
+
+/// Runs `f` with the keys the focused widget inside of the container didn't handle.
+/// The first handler is run before the children, so the focused widget can mark the container, see `FocusState::ancestors`.
+pub fn key_handler(mut f: impl FnMut(&Key, &Modifiers) -> bool + 'static) -> (Handler, Handler) {
+    let enter = Handler::new(|ctx, _| ctx.focus.enter());
+    let exit = Handler::new(move |ctx, _| {
+        if ctx.focus.exit() {
+            ctx.focus.handle(&mut f);
+        }
+    });
+    (enter, exit)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn focus() {
+        let mut focus = FocusState::default();
+        let shift = Modifiers {
+            shift: true,
+            ..Default::default()
+        };
+        let id = |n| (("item", 0, 0, 0), n);
+        for i in 0..3 {
+            focus.register(id(i));
+        }
+        focus.next_frame();
+
+        focus.record(&Key::Tab, &Modifiers::default(), 0);
+        assert_eq!(focus.focused, Some(id(0)));
+        assert!(focus.visible);
+        focus.record(&Key::Tab, &shift, 0);
+        assert_eq!(focus.focused, Some(id(2)));
+        focus.record(&Key::Tab, &Modifiers::default(), 0);
+        assert_eq!(focus.focused, Some(id(0)));
+
+        //The focused widget handles enter, the container it's drawn inside of gets the rest.
+        focus.record(&Key::Enter, &Modifiers::default(), 0);
+        focus.record(&Key::Escape, &Modifiers::default(), 0);
+        focus.enter();
+        focus.enter();
+        assert!(!focus.exit());
+        focus.drawn();
+        focus.handle(|key, _| *key == Key::Enter);
+        assert!(focus.pending());
+
+        let mut escaped = false;
+        assert!(focus.exit());
+        focus.handle(|key, _| {
+            escaped = *key == Key::Escape;
+            escaped
+        });
+        assert!(escaped);
+        assert!(!focus.pending());
+        assert!(!focus.exit());
+
+        //A widget added in front of the focused one doesn't move the focus.
+        focus.focus(id(2));
+        focus.register((("new", 0, 0, 0), 0));
+        for i in 0..3 {
+            focus.register(id(i));
+        }
+        focus.next_frame();
+        assert_eq!(focus.focused, Some(id(2)));
+
+        //The focused widget went away.
+        focus.register(id(0));
+        focus.next_frame();
+        assert_eq!(focus.focused, None);
+        assert!(focus.keys.is_empty());
+    }
+}
@@ -0,0 +1,481 @@
+//! Two dimensional layout, widgets are placed into cells of rows and columns.
+//!
+//! ```rs
+//! grid!(text("Name"), input, text("Email"), input2)
+//!     .columns([Track::Auto, Track::Fr(1.0)])
+//!     .gap(8)
+//!     .w(400);
+//! ```
+use crate::*;
+
+/// The size of a row or column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Track {
+    Px(usize),
+    /// Share of the space left over after every other track.
+    /// Behaves like `Auto` when the grid doesn't have a fixed size.
+    Fr(f32),
+    /// As large as the largest widget in the track.
+    Auto,
+    /// `Auto`, but never smaller than the first or larger than the second size.
+    MinMax(usize, usize),
+}
+
+/// Where a widget goes in a `grid!`. Widgets without a row or column fill the next free cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridCell {
+    pub column: Option<usize>,
+    pub row: Option<usize>,
+    pub column_span: usize,
+    pub row_span: usize,
+}
+
+impl GridCell {
+    pub const fn new() -> Self {
+        Self {
+            column: None,
+            row: None,
+            column_span: 1,
+            row_span: 1,
+        }
+    }
+    /// Spans are at least one track and can't go past the last column, the same as CSS.
+    pub fn clamp_span(mut self, columns: usize) -> Self {
+        let columns = columns.max(1);
+        let column = self.column.map_or(0, |column| column.min(columns - 1));
+        self.column_span = self.column_span.clamp(1, columns - column);
+        self.row_span = self.row_span.max(1);
+        self
+    }
+}
+
+impl Default for GridCell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The (column, row) of every cell.
+/// Widgets with a position are placed first, the rest are placed row by row into the gaps.
+pub fn place_items(cells: &[GridCell], columns: usize) -> Vec<(usize, usize)> {
+    let columns = columns.max(1);
+    let cells: Vec<GridCell> = cells.iter().map(|cell| cell.clamp_span(columns)).collect();
+    let mut occupied: Vec<Vec<bool>> = Vec::new();
+    let mut placed = vec![(0, 0); cells.len()];
+
+    let fits = |occupied: &Vec<Vec<bool>>, column: usize, row: usize, cell: &GridCell| {
+        column + cell.column_span.min(columns) <= columns
+            && (row..row + cell.row_span).all(|r| {
+                (column..column + cell.column_span.min(columns))
+                    .all(|c| !occupied.get(r).map(|row| row[c]).unwrap_or(false))
+            })
+    };
+    let occupy = |occupied: &mut Vec<Vec<bool>>, column: usize, row: usize, cell: &GridCell| {
+        for r in row..row + cell.row_span {
+            if occupied.len() <= r {
+                occupied.resize(r + 1, vec![false; columns]);
+            }
+            let end = (column + cell.column_span).min(columns);
+            occupied[r][column..end].fill(true);
+        }
+    };
+
+    for (i, cell) in cells.iter().enumerate() {
+        if let (Some(column), Some(row)) = (cell.column, cell.row) {
+            let column = column.min(columns - 1);
+            occupy(&mut occupied, column, row, cell);
+            placed[i] = (column, row);
+        }
+    }
+
+    //Fully automatic widgets never go backwards, the same as CSS.
+    let mut cursor = (0, 0);
+    for (i, cell) in cells.iter().enumerate() {
+        if cell.column.is_some() && cell.row.is_some() {
+            continue;
+        }
+
+        let (mut column, mut row) = match (cell.column, cell.row) {
+            (Some(column), None) => (column.min(columns - 1), 0),
+            (None, Some(row)) => (0, row),
+            _ => cursor,
+        };
+
+        while !fits(&occupied, column, row, cell) {
+            match (cell.column, cell.row) {
+                (Some(_), None) => row += 1,
+                (None, Some(_)) if column + 1 < columns => column += 1,
+                //The row is full, put it on the next one.
+                (None, Some(_)) => {
+                    column = 0;
+                    row += 1;
+                }
+                _ if column + 1 < columns => column += 1,
+                _ => {
+                    column = 0;
+                    row += 1;
+                }
+            }
+        }
+
+        occupy(&mut occupied, column, row, cell);
+        placed[i] = (column, row);
+        if cell.column.is_none() && cell.row.is_none() {
+            cursor = (column, row);
+        }
+    }
+
+    placed
+}
+
+/// The size of every track.
+/// `items` are the first track, number of tracks spanned and the size of each widget.
+pub fn size_tracks(
+    tracks: &[Track],
+    items: &[(usize, usize, usize)],
+    gap: usize,
+    available: Option<usize>,
+) -> Vec<usize> {
+    let fits_content = |track: &Track| match track {
+        Track::Auto | Track::MinMax(..) => true,
+        Track::Fr(_) => available.is_none(),
+        Track::Px(_) => false,
+    };
+
+    let mut sizes: Vec<usize> = tracks
+        .iter()
+        .map(|track| match track {
+            Track::Px(px) => *px,
+            Track::MinMax(min, _) => *min,
+            _ => 0,
+        })
+        .collect();
+
+    for &(start, span, size) in items.iter().filter(|(_, span, _)| *span == 1) {
+        match tracks[start] {
+            Track::MinMax(min, max) => sizes[start] = sizes[start].max(size.clamp(min, max.max(min))),
+            ref track if fits_content(track) => sizes[start] = sizes[start].max(size),
+            _ => {}
+        }
+    }
+
+    //Widgets spanning multiple tracks share the extra size between the ones that fit their content.
+    for &(start, span, size) in items.iter().filter(|(_, span, _)| *span > 1) {
+        let range = start..(start + span).min(tracks.len());
+        let current = sizes[range.clone()].iter().sum::<usize>() + gap * (range.len() - 1);
+        let growable: Vec<usize> = range.filter(|i| fits_content(&tracks[*i])).collect();
+        if size > current && !growable.is_empty() {
+            let extra = (size - current).div_ceil(growable.len());
+            for i in growable {
+                sizes[i] += extra;
+            }
+        }
+    }
+
+    if let Some(available) = available {
+        let used = tracks
+            .iter()
+            .zip(&sizes)
+            .filter(|(track, _)| !matches!(track, Track::Fr(_)))
+            .map(|(_, size)| size)
+            .sum::<usize>()
+            + gap * tracks.len().saturating_sub(1);
+        let free = available.saturating_sub(used) as f32;
+        let total: f32 = tracks
+            .iter()
+            .map(|track| if let Track::Fr(fr) = track { *fr } else { 0.0 })
+            .sum();
+
+        for (track, size) in tracks.iter().zip(&mut sizes) {
+            if let Track::Fr(fr) = track {
+                //Like CSS, fractions that add up to less than one leave some space empty.
+                *size = (free * fr / total.max(1.0)).round() as usize;
+            }
+        }
+    }
+
+    sizes
+}
+
+/// The start of every track, including gaps.
+fn track_offsets(sizes: &[usize], gap: usize) -> Vec<usize> {
+    let mut offset = 0;
+    sizes
+        .iter()
+        .map(|size| {
+            let start = offset;
+            offset += size + gap;
+            start
+        })
+        .collect()
+}
+
+#[macro_export]
+macro_rules! grid {
+    ($($widget:expr),* $(,)?) => {{
+        let f = || {
+            let mut widgets = Vec::new();
+            $(
+                $crate::Child::push_into(&mut $widget, &mut widgets);
+            )*
+            widgets
+        };
+
+        $crate::DeferGrid {
+            f,
+            columns: Vec::new(),
+            rows: Vec::new(),
+            column_gap: 0,
+            row_gap: 0,
+            align_items: $crate::AlignItems::Start,
+            width: None,
+            height: None,
+            bg: None,
+            nested: false,
+        }
+    }};
+}
+
+pub struct DeferGrid<F: FnMut() -> Vec<TypelessWidget>> {
+    pub f: F,
+    /// A single `Auto` column is used when empty.
+    pub columns: Vec<Track>,
+    /// Rows that aren't in the template are `Auto`.
+    pub rows: Vec<Track>,
+    pub column_gap: usize,
+    pub row_gap: usize,
+    /// Alignment of the widget inside it's cell, on both axes.
+    pub align_items: AlignItems,
+    /// `Fr` tracks need a fixed size to share out.
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub bg: Option<Color>,
+    /// Set when the grid is inside of another layout, which draws it instead.
+    pub nested: bool,
+}
+
+impl<F: FnMut() -> Vec<TypelessWidget>> Drop for DeferGrid<F> {
+    fn drop(&mut self) {
+        if !self.nested {
+            self.draw();
+        }
+    }
+}
+
+impl<F: FnMut() -> Vec<TypelessWidget>> Child for DeferGrid<F> {
+    fn push_into(&mut self, widgets: &mut Vec<TypelessWidget>) {
+        self.nested = true;
+        let mut flex = self.build();
+        if let Some(bg) = self.bg {
+            flex.commands.insert(
+                0,
+                Command {
+                    area: flex.area,
+                    primative: Primative::Ellipse(0, bg),
+                },
+            );
+        }
+        widgets.push(Container::from(flex).into());
+    }
+}
+
+impl<F: FnMut() -> Vec<TypelessWidget>> DeferGrid<F> {
+    pub fn draw(&mut self) {
+        let flex = self.build();
+
+        if let Some(bg) = self.bg {
+            queue_command(flex.area, Primative::Ellipse(0, bg))
+        };
+
+        for cmd in flex.commands {
+            cmd.queue();
+        }
+    }
+    pub fn columns(mut self, columns: impl IntoIterator<Item = Track>) -> Self {
+        self.columns = columns.into_iter().collect();
+        self
+    }
+    pub fn rows(mut self, rows: impl IntoIterator<Item = Track>) -> Self {
+        self.rows = rows.into_iter().collect();
+        self
+    }
+    pub fn gap(mut self, gap: usize) -> Self {
+        self.column_gap = gap;
+        self.row_gap = gap;
+        self
+    }
+    pub fn column_gap(mut self, gap: usize) -> Self {
+        self.column_gap = gap;
+        self
+    }
+    pub fn row_gap(mut self, gap: usize) -> Self {
+        self.row_gap = gap;
+        self
+    }
+    pub fn align_items(mut self, align_items: AlignItems) -> Self {
+        self.align_items = align_items;
+        self
+    }
+    pub fn w(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+    pub fn h(mut self, height: usize) -> Self {
+        self.height = Some(height);
+        self
+    }
+    pub fn bg(mut self, color: Color) -> Self {
+        self.bg = Some(color);
+        self
+    }
+}
+
+impl<F: FnMut() -> Vec<TypelessWidget>> Defer for DeferGrid<F> {
+    type T = Flex;
+    fn build(&mut self) -> Self::T {
+        let mut widgets = (self.f)();
+        let mut columns = self.columns.clone();
+        if columns.is_empty() {
+            columns.push(Track::Auto);
+        }
+
+        let cells: Vec<GridCell> = widgets.iter().map(|w| w.item.cell.clamp_span(columns.len())).collect();
+        let placed = place_items(&cells, columns.len());
+
+        let row_count = placed
+            .iter()
+            .zip(&cells)
+            .map(|((_, row), cell)| row + cell.row_span)
+            .max()
+            .unwrap_or(0);
+        let mut rows = self.rows.clone();
+        if rows.len() < row_count {
+            rows.resize(row_count, Track::Auto);
+        }
+
+        let column_items: Vec<(usize, usize, usize)> = widgets
+            .iter()
+            .zip(&placed)
+            .zip(&cells)
+            .map(|((w, (column, _)), cell)| (*column, cell.column_span, w.area.width + w.item.margin.total(true).0))
+            .collect();
+        let row_items: Vec<(usize, usize, usize)> = widgets
+            .iter()
+            .zip(&placed)
+            .zip(&cells)
+            .map(|((w, (_, row)), cell)| (*row, cell.row_span, w.area.height + w.item.margin.total(false).0))
+            .collect();
+
+        let column_sizes = size_tracks(&columns, &column_items, self.column_gap, self.width);
+        let row_sizes = size_tracks(&rows, &row_items, self.row_gap, self.height);
+        let column_offsets = track_offsets(&column_sizes, self.column_gap);
+        let row_offsets = track_offsets(&row_sizes, self.row_gap);
+
+        let span = |sizes: &[usize], start: usize, count: usize, gap: usize| {
+            sizes[start..start + count].iter().sum::<usize>() + gap * (count - 1)
+        };
+
+        let mut commands = Vec::with_capacity(widgets.len());
+        for ((widget, (column, row)), cell) in widgets.iter_mut().zip(&placed).zip(&cells) {
+            let cell_width = span(&column_sizes, *column, cell.column_span, self.column_gap);
+            let cell_height = span(&row_sizes, *row, cell.row_span, self.row_gap);
+            let margin = widget.item.margin;
+            let inner_width = cell_width.saturating_sub(margin.left + margin.right);
+            let inner_height = cell_height.saturating_sub(margin.top + margin.bottom);
+
+            let horizontal = widget.item.justify_self.unwrap_or(self.align_items);
+            let vertical = widget.item.align_self.unwrap_or(self.align_items);
+
+            let mut area = widget.area;
+            if horizontal == AlignItems::Stretch {
+                area.width = inner_width;
+            }
+            if vertical == AlignItems::Stretch {
+                area.height = inner_height;
+            }
+            area.x = column_offsets[*column] + margin.left + align_cross(horizontal, inner_width, area.width, 0);
+            area.y = row_offsets[*row] + margin.top + align_cross(vertical, inner_height, area.height, 0);
+            push_command(&mut commands, widget, area);
+        }
+
+        let total = |sizes: &[usize], gap: usize| sizes.iter().sum::<usize>() + gap * sizes.len().saturating_sub(1);
+        let width = self.width.unwrap_or_else(|| total(&column_sizes, self.column_gap));
+        let height = self.height.unwrap_or_else(|| total(&row_sizes, self.row_gap));
+
+        Flex {
+            commands,
+            area: Rect::new(0, 0, width, height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn tracks() {
+        let tracks = [Track::Px(50), Track::Auto, Track::Fr(1.0), Track::Fr(2.0)];
+        let items = [(1, 1, 30), (0, 1, 100)];
+        assert_eq!(size_tracks(&tracks, &items, 10, Some(290)), [50, 30, 60, 120]);
+
+        //Fractions fit their content without a size.
+        assert_eq!(size_tracks(&tracks, &[(2, 1, 20)], 0, None), [50, 0, 20, 0]);
+
+        assert_eq!(size_tracks(&[Track::MinMax(20, 40)], &[(0, 1, 100)], 0, None), [40]);
+
+        //The spanning widget grows the auto track.
+        let tracks = [Track::Px(10), Track::Auto];
+        assert_eq!(size_tracks(&tracks, &[(0, 2, 50)], 5, None), [10, 35]);
+    }
+
+    #[test]
+    fn placement() {
+        let auto = GridCell::new();
+        let fixed = GridCell {
+            column: Some(1),
+            row: Some(0),
+            ..GridCell::new()
+        };
+        let wide = GridCell {
+            column_span: 2,
+            ..GridCell::new()
+        };
+
+        assert_eq!(place_items(&[auto, auto, auto], 2), [(0, 0), (1, 0), (0, 1)]);
+        assert_eq!(place_items(&[auto, fixed, auto], 2), [(0, 0), (1, 0), (0, 1)]);
+        assert_eq!(place_items(&[auto, wide], 2), [(0, 0), (0, 1)]);
+
+        //The span is cut off at the last column instead of looking for a row it fits in.
+        let overflowing = GridCell {
+            column: Some(1),
+            column_span: 2,
+            ..GridCell::new()
+        };
+        assert_eq!(place_items(&[auto, overflowing], 2), [(0, 0), (1, 0)]);
+        assert_eq!(overflowing.clamp_span(2).column_span, 1);
+    }
+
+    #[test]
+    fn layout() {
+        let flex = grid!(rect().wh(10), rect().wh(20), rect().w(10).h(10).column_span(2))
+            .columns([Track::Auto, Track::Fr(1.0)])
+            .gap(5)
+            .w(100)
+            .build();
+
+        assert_eq!(flex.area, Rect::new(0, 0, 100, 20 + 5 + 10));
+        assert_eq!((flex.commands[1].area.x, flex.commands[1].area.y), (15, 0));
+        assert_eq!((flex.commands[2].area.x, flex.commands[2].area.y), (0, 25));
+    }
+
+    #[test]
+    fn overflowing_span() {
+        let flex = grid!(rect().wh(10), rect().wh(10).column(1).row(0).column_span(2))
+            .columns([Track::Auto, Track::Auto])
+            .build();
+
+        assert_eq!(flex.area, Rect::new(0, 0, 20, 10));
+        assert_eq!((flex.commands[1].area.x, flex.commands[1].area.y), (10, 0));
+    }
+}
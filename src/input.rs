@@ -1,29 +1,376 @@
 use crate::*;
+use std::{
+    cell::RefCell,
+    panic::Location,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 //TODO: Did this not get checked out?
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MouseAction {
     Pressed,
     Released,
     Clicked,
+    DoubleClicked,
+    /// Held down for `ClickState::long_press_time`.
+    LongPressed,
+    /// The button isn't used for these, only the mouse position.
+    Hovered,
+    Entered,
+    Exited,
+    /// Only the left mouse can drag.
+    DragStarted,
+    Dragged,
+    DragEnded,
+    Dropped,
 }
 
+/// What runs the function of a `Click`, the button is only used by mouse actions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Mouse(MouseAction),
+    /// Checks the payload before any `Dropped` handlers are run.
+    Accepts,
+    /// Keyboard focus, see `FocusState`.
+    Focusable,
+    FocusRequested,
+    KeyPressed,
+    /// Run every frame the widget is drawn, used to record it's shortcuts.
+    Drawn,
+}
+
+impl From<MouseAction> for Action {
+    fn from(action: MouseAction) -> Self {
+        Action::Mouse(action)
+    }
+}
+
+impl Action {
+    /// Widgets with these can be dragged.
+    pub const fn is_drag(self) -> bool {
+        matches!(
+            self,
+            Self::Mouse(MouseAction::DragStarted | MouseAction::Dragged | MouseAction::DragEnded)
+        )
+    }
+
+    /// Widgets with these can be focused.
+    pub const fn is_focus(self) -> bool {
+        matches!(self, Self::Focusable | Self::FocusRequested | Self::KeyPressed)
+    }
+}
+
+/// Shared between the copies of a widget, state the closure changes has to be kept in an `Rc<RefCell<_>>`.
+///
+/// Handlers run when the frame is drawn, after the layout that added them is gone,
+/// so they can't borrow from the caller. Borrowing app state would need a scoped frame, which doesn't exist yet.
+pub type ClickFn<T> = Rc<RefCell<dyn FnMut(&mut T)>>;
+
+/// (file, line, column, id), where the first behaviour of a widget was added and the id given to `Widget::id`.
+pub type WidgetId = (&'static str, u32, u32, usize);
+
 //TODO: Expand to support keyboard input as well.
 pub struct Click<T> {
     pub button: MouseButton,
-    pub action: MouseAction,
-    pub function: fn(&mut T),
+    pub action: Action,
+    pub function: ClickFn<T>,
+    /// Where the behaviour was added, see `WidgetId`.
+    pub location: &'static Location<'static>,
 }
 
-pub fn clicked(ctx: &mut Context, area: Rect, button: MouseButton) -> bool {
+impl<T> Clone for Click<T> {
+    fn clone(&self) -> Self {
+        Self {
+            button: self.button,
+            action: self.action,
+            function: self.function.clone(),
+            location: self.location,
+        }
+    }
+}
+
+impl<T> Click<T> {
+    #[track_caller]
+    pub fn new(button: MouseButton, action: impl Into<Action>, function: impl FnMut(&mut T) + 'static) -> Self {
+        Self {
+            button,
+            action: action.into(),
+            function: Rc::new(RefCell::new(function)),
+            location: Location::caller(),
+        }
+    }
+
+    pub fn call(&self, widget: &mut T) {
+        (self.function.borrow_mut())(widget)
+    }
+}
+
+pub type HandlerFn = Rc<RefCell<dyn FnMut(&mut Context, Rect)>>;
+
+/// Runs the input behaviour of a widget once it's been laid out, see `Primative::Handler`.
+/// Only run on the thread that queues the layout.
+#[derive(Clone)]
+pub struct Handler(pub HandlerFn);
+
+impl Handler {
+    pub fn new(f: impl FnMut(&mut Context, Rect) + 'static) -> Self {
+        Self(Rc::new(RefCell::new(f)))
+    }
+
+    pub fn run(&self, ctx: &mut Context, area: Rect) {
+        (self.0.borrow_mut())(ctx, area)
+    }
+}
+
+impl std::fmt::Debug for Handler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Handler").finish()
+    }
+}
+
+/// Handles the clicks of a copy of `widget`, the copy is moved to the area the widget ends up in.
+/// Widgets without any behaviour don't need a handler.
+pub fn click_handler<T: Widget + Clone + 'static>(widget: &T) -> Option<Handler> {
+    let mut widget = widget.clone();
+    if widget.behaviour().is_none_or(|b| b.is_empty()) {
+        return None;
+    }
+    Some(Handler::new(move |ctx, area| {
+        if let Some(a) = widget.area_mut() {
+            *a = area;
+        }
+        widget.run_click(ctx);
+    }))
+}
+
+/// A widget registered with `HoverState` and how many with the same id were registered before it that frame,
+/// so widgets created in a loop without an id are still kept apart.
+pub type HoverId = (WidgetId, usize);
+
+/// Widgets that can be hovered register their area every frame, the last one under the mouse is on top.
+/// The widgets drawn after this one aren't known yet, so the areas from the previous frame are used.
+#[derive(Debug, Default)]
+pub struct HoverState {
+    /// Registered this frame.
+    pub areas: Vec<(HoverId, Rect)>,
+    /// Registered last frame, in the order they were drawn.
+    pub previous: Vec<(HoverId, Rect)>,
+    /// What was hovered last frame.
+    pub last: Option<HoverId>,
+}
+
+impl HoverState {
+    /// Registering the same widget twice in a row gives back the same id.
+    pub fn register(&mut self, id: WidgetId, area: Rect) -> HoverId {
+        if let Some(&((last, n), last_area)) = self.areas.last() {
+            if last == id && last_area == area {
+                return (id, n);
+            }
+        }
+        let n = self.areas.iter().filter(|((i, _), _)| *i == id).count();
+        self.areas.push(((id, n), area));
+        (id, n)
+    }
+
+    /// The topmost widget under the mouse.
+    pub fn top(&self, mouse: Rect) -> Option<(HoverId, Rect)> {
+        self.previous
+            .iter()
+            .rev()
+            .find(|(_, area)| area.intersects(mouse))
+            .copied()
+    }
+
+    pub fn hovered(&self, mouse: Rect, id: HoverId) -> bool {
+        self.top(mouse).is_some_and(|(top, _)| top == id)
+    }
+
+    pub fn entered(&self, mouse: Rect, id: HoverId) -> bool {
+        self.hovered(mouse, id) && self.last != Some(id)
+    }
+
+    pub fn exited(&self, mouse: Rect, id: HoverId) -> bool {
+        self.last == Some(id) && !self.hovered(mouse, id)
+    }
+
+    /// Called once all of the widgets have been drawn.
+    pub fn next_frame(&mut self, mouse: Rect) {
+        self.last = self.top(mouse).map(|(id, _)| id);
+        self.previous = std::mem::take(&mut self.areas);
+    }
+}
+
+/// Only the topmost widget under the mouse is hovered.
+pub fn hovered(ctx: &mut Context, id: WidgetId, area: Rect) -> bool {
+    let id = ctx.hover.register(id, area);
+    ctx.hover.hovered(ctx.window.mouse_position, id)
+}
+
+/// The mouse moved onto the widget this frame.
+pub fn entered(ctx: &mut Context, id: WidgetId, area: Rect) -> bool {
+    let id = ctx.hover.register(id, area);
+    ctx.hover.entered(ctx.window.mouse_position, id)
+}
+
+/// The mouse moved off of the widget, or another widget was drawn on top of it.
+pub fn exited(ctx: &mut Context, id: WidgetId, area: Rect) -> bool {
+    let id = ctx.hover.register(id, area);
+    ctx.hover.exited(ctx.window.mouse_position, id)
+}
+
+/// Double clicks and long presses of one mouse button, worked out from when it's pressed and let go
+/// so every window backend behaves the same.
+#[derive(Debug, Clone, Copy)]
+pub struct ClickState {
+    pub down: bool,
+    pub press_time: Option<Instant>,
+    pub press_position: Rect,
+    pub release_time: Option<Instant>,
+    pub release_position: Option<Rect>,
+    /// Presses in a row that were close enough together, reset to one when they're not.
+    pub clicks: usize,
+    /// Longest time between letting go and pressing again that still counts as the same click.
+    pub double_click_time: Duration,
+    /// How far the mouse can move between clicks.
+    pub double_click_distance: usize,
+    pub long_press_time: Duration,
+    /// Set when the second click is let go, until it's handled or the frame ends.
+    pub double_clicked: bool,
+    /// Long presses are only handled once per press.
+    pub long_pressed: bool,
+}
+
+impl Default for ClickState {
+    fn default() -> Self {
+        Self {
+            down: false,
+            press_time: None,
+            press_position: Rect::new(0, 0, 0, 0),
+            release_time: None,
+            release_position: None,
+            clicks: 0,
+            double_click_time: Duration::from_millis(500),
+            double_click_distance: 4,
+            long_press_time: Duration::from_millis(500),
+            double_clicked: false,
+            long_pressed: false,
+        }
+    }
+}
+
+impl ClickState {
+    /// Follow the button of the window, `pressed` and `released` are only called when it changes.
+    pub fn update(&mut self, down: bool, mouse: Rect) {
+        if down && !self.down {
+            self.pressed(mouse);
+        } else if !down && self.down {
+            self.released(mouse);
+        }
+    }
+
+    pub fn pressed(&mut self, mouse: Rect) {
+        let now = Instant::now();
+        let again = self
+            .release_time
+            .is_some_and(|time| now - time <= self.double_click_time)
+            && self.release_position.is_some_and(|last| {
+                last.x.abs_diff(mouse.x) <= self.double_click_distance
+                    && last.y.abs_diff(mouse.y) <= self.double_click_distance
+            });
+        self.clicks = if again { self.clicks + 1 } else { 1 };
+        self.down = true;
+        self.press_time = Some(now);
+        self.press_position = mouse;
+        self.release_position = None;
+        self.long_pressed = false;
+    }
+
+    pub fn released(&mut self, mouse: Rect) {
+        self.down = false;
+        self.release_time = Some(Instant::now());
+        self.release_position = Some(mouse);
+        self.double_clicked = self.clicks == 2;
+    }
+
+    /// The second click in a row was let go on top of the area.
+    pub fn double_clicked_in(&mut self, area: Rect) -> bool {
+        if self.double_clicked && self.press_position.intersects(area) {
+            self.double_clicked = false;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Held down on top of the area for `long_press_time`.
+    pub fn long_pressed_in(&mut self, area: Rect) -> bool {
+        let held = self
+            .press_time
+            .is_some_and(|time| time.elapsed() >= self.long_press_time);
+        if self.down && held && !self.long_pressed && self.press_position.intersects(area) {
+            self.long_pressed = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Called once all of the widgets have been drawn, a double click that nothing handled is dropped.
+    pub fn next_frame(&mut self) {
+        self.double_clicked = false;
+    }
+}
+
+/// Feed the mouse buttons of the window into `Context::clicks`, called after every event.
+pub fn update_clicks(ctx: &mut Context) {
+    let window = &ctx.window;
+    let buttons = [
+        &window.left_mouse,
+        &window.right_mouse,
+        &window.middle_mouse,
+        &window.mouse_4,
+        &window.mouse_5,
+    ];
+    for (clicks, mouse) in ctx.clicks.iter_mut().zip(buttons) {
+        clicks.update(mouse.pressed, window.mouse_position);
+    }
+}
+
+pub fn click_state(ctx: &mut Context, button: MouseButton) -> &mut ClickState {
+    let index = match button {
+        MouseButton::Left => 0,
+        MouseButton::Right => 1,
+        MouseButton::Middle => 2,
+        MouseButton::Mouse4 => 3,
+        MouseButton::Mouse5 => 4,
+    };
+    &mut ctx.clicks[index]
+}
+
+pub fn mouse_state(ctx: &mut Context, button: MouseButton) -> &mut MouseState {
     match button {
-        MouseButton::Left => ctx.window.left_mouse.clicked(area),
-        MouseButton::Right => ctx.window.right_mouse.clicked(area),
-        MouseButton::Middle => ctx.window.middle_mouse.clicked(area),
-        MouseButton::Mouse4 => ctx.window.mouse_4.clicked(area),
-        MouseButton::Mouse5 => ctx.window.mouse_5.clicked(area),
+        MouseButton::Left => &mut ctx.window.left_mouse,
+        MouseButton::Right => &mut ctx.window.right_mouse,
+        MouseButton::Middle => &mut ctx.window.middle_mouse,
+        MouseButton::Mouse4 => &mut ctx.window.mouse_4,
+        MouseButton::Mouse5 => &mut ctx.window.mouse_5,
     }
 }
 
+pub fn clicked(ctx: &mut Context, area: Rect, button: MouseButton) -> bool {
+    mouse_state(ctx, button).clicked(area)
+}
+
+/// Doesn't stop `clicked` from being true for the same click.
+pub fn double_clicked(ctx: &mut Context, area: Rect, button: MouseButton) -> bool {
+    click_state(ctx, button).double_clicked_in(area)
+}
+
+pub fn long_pressed(ctx: &mut Context, area: Rect, button: MouseButton) -> bool {
+    click_state(ctx, button).long_pressed_in(area)
+}
+
 pub fn pressed(ctx: &Context, area: Rect, button: MouseButton) -> bool {
     if !ctx.window.mouse_position.intersects(area) {
         return false;
@@ -51,3 +398,105 @@ pub fn released(ctx: &Context, area: Rect, button: MouseButton) -> bool {
         MouseButton::Mouse5 => ctx.window.mouse_5.pressed,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn hover() {
+        let mut hover = HoverState::default();
+        let (below, above) = (("below", 0, 0, 0), ("above", 0, 0, 0));
+        let (b, a) = (Rect::new(0, 0, 20, 20), Rect::new(10, 10, 20, 20));
+        let (mouse, outside) = (Rect::new(15, 15, 1, 1), Rect::new(50, 50, 1, 1));
+
+        //Nothing is known about the first frame.
+        let below = hover.register(below, b);
+        let above = hover.register(above, a);
+        assert!(!hover.hovered(mouse, above));
+        hover.next_frame(mouse);
+
+        //Only the widget drawn last is hovered.
+        assert!(hover.hovered(mouse, above));
+        assert!(!hover.hovered(mouse, below));
+        assert!(hover.entered(mouse, above));
+        hover.register(below.0, b);
+        hover.register(above.0, a);
+        hover.next_frame(mouse);
+
+        //Moving the widget doesn't leave and enter it again.
+        assert!(hover.hovered(mouse, above));
+        assert!(!hover.entered(mouse, above));
+        hover.register(below.0, b);
+        hover.register(above.0, Rect::new(12, 12, 20, 20));
+        hover.next_frame(mouse);
+
+        assert!(hover.hovered(mouse, above));
+        assert!(!hover.exited(mouse, above));
+        assert!(hover.exited(outside, above));
+        assert!(!hover.exited(outside, below));
+
+        //The widget on top went away, so the one below is hovered.
+        hover.register(below.0, b);
+        hover.next_frame(mouse);
+        assert!(hover.hovered(mouse, below));
+        assert!(hover.exited(mouse, above));
+
+        //Widgets with the same area or the same id are kept apart.
+        let item = ("item", 0, 0, 0);
+        assert_eq!(hover.register(below.0, b), below);
+        assert_eq!(hover.register(below.0, b), below);
+        assert_ne!(hover.register(above.0, b), hover.register(item, b));
+        assert_eq!(hover.register(item, a), (item, 1));
+        hover.next_frame(mouse);
+        assert!(hover.hovered(mouse, (item, 1)));
+        assert!(!hover.hovered(mouse, (item, 0)));
+    }
+
+    #[test]
+    fn clicks() {
+        let area = Rect::new(0, 0, 10, 10);
+        let (mouse, far) = (Rect::new(2, 2, 1, 1), Rect::new(9, 9, 1, 1));
+        let mut state = ClickState::default();
+
+        //Holding the button down is still one press.
+        state.update(true, mouse);
+        state.update(true, far);
+        state.update(false, mouse);
+        assert_eq!(state.clicks, 1);
+        assert_eq!(state.press_position, mouse);
+        assert!(!state.double_clicked_in(area));
+
+        state.update(true, mouse);
+        state.update(false, mouse);
+        assert_eq!(state.clicks, 2);
+        assert!(state.double_clicked_in(area));
+        assert!(!state.double_clicked_in(area));
+
+        //Nothing handled it by the end of the frame.
+        let mut other = ClickState::default();
+        for _ in 0..2 {
+            other.update(true, mouse);
+            other.update(false, mouse);
+        }
+        other.next_frame();
+        assert!(!other.double_clicked_in(area));
+
+        //Too far away from the last click.
+        state.update(true, far);
+        state.update(false, far);
+        assert_eq!(state.clicks, 1);
+
+        state.double_click_time = std::time::Duration::ZERO;
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        state.update(true, far);
+        assert_eq!(state.clicks, 1);
+
+        //Only once per press.
+        state.long_press_time = std::time::Duration::ZERO;
+        assert!(state.long_pressed_in(area));
+        assert!(!state.long_pressed_in(area));
+        state.update(false, far);
+        assert!(!state.long_pressed_in(area));
+    }
+}
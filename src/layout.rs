@@ -27,34 +27,94 @@ use crate::*;
 //     }
 // }
 
-//Unused
+/// The size of the window before display scaling, empty when there is no context.
+pub fn viewport() -> Rect {
+    match unsafe { CTX.as_ref() } {
+        Some(ctx) => {
+            let scale = ctx.window.display_scale();
+            Rect::new(
+                0,
+                0,
+                (ctx.window.width() as f32 / scale).round() as usize,
+                (ctx.window.height() as f32 / scale).round() as usize,
+            )
+        }
+        None => Rect::default(),
+    }
+}
+
 pub trait Metrics {
     fn em(self) -> Unit;
-    fn vh(self) -> Self;
-    fn vw(self) -> Self;
+    fn vh(self) -> Unit;
+    fn vw(self) -> Unit;
 }
 
 /// 10.vh() will be 10% of viewport height.
 impl Metrics for usize {
+    fn em(self) -> Unit {
+        Unit::Em(self as f32)
+    }
+    fn vh(self) -> Unit {
+        Unit::Vh(self as f32)
+    }
+    fn vw(self) -> Unit {
+        Unit::Vw(self as f32)
+    }
+}
+
+impl Metrics for f32 {
     fn em(self) -> Unit {
         Unit::Em(self)
     }
-    fn vh(self) -> Self {
-        todo!()
+    fn vh(self) -> Unit {
+        Unit::Vh(self)
     }
-    fn vw(self) -> Self {
-        todo!()
+    fn vw(self) -> Unit {
+        Unit::Vw(self)
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Unit {
     Px(usize),
+    /// Physical pixels, display scaling is not applied.
+    ///
+    /// Scaling may not be pixel accurate, `107 * 1.5 = 160.5 rounded 161` but `(107 - 1) * 1.5 = 159`.
+    /// If the user wanted a 1px gap at the edge of the window, rounding would create a 2px gap.
+    Unscaled(usize),
     ///Relative to the font-size of the element
     ///https://en.wikipedia.org/wiki/Em_(typography)
     ///https://www.w3schools.com/cssref/css_units.php
-    Em(usize),
-    //Percentage relative to what?
-    Percentage(usize),
+    Em(f32),
+    /// Relative to the parent container, or the window when there isn't one.
+    Percentage(f32),
+    /// Percentage of the window width.
+    Vw(f32),
+    /// Percentage of the window height.
+    Vh(f32),
+}
+
+impl Unit {
+    /// The length in pixels before display scaling.
+    /// `parent` is used for percentages and `em` is the font size of the widget.
+    pub fn resolve(self, parent: usize, em: usize) -> usize {
+        let percent = |length: usize, p: f32| (length as f32 * p / 100.0).round() as usize;
+        match self {
+            Unit::Px(px) => px,
+            Unit::Unscaled(px) => (px as f32 / viewport_scale()).round() as usize,
+            Unit::Em(value) => (em as f32 * value).round() as usize,
+            Unit::Percentage(p) => percent(parent, p),
+            Unit::Vw(p) => percent(viewport().width, p),
+            Unit::Vh(p) => percent(viewport().height, p),
+        }
+    }
+}
+
+/// The display scale of the window, `1.0` when there is no context.
+pub fn viewport_scale() -> f32 {
+    unsafe { CTX.as_ref() }
+        .map(|ctx| ctx.window.display_scale())
+        .unwrap_or(1.0)
 }
 
 impl From<usize> for Unit {
@@ -71,7 +131,7 @@ impl From<i32> for Unit {
 
 impl From<f32> for Unit {
     fn from(val: f32) -> Self {
-        Unit::Percentage((val * 100.0) as usize)
+        Unit::Percentage(val * 100.0)
     }
 }
 
@@ -81,9 +141,96 @@ pub enum Button {
     Right,
 }
 
+/// How a widget inside a container is placed, the same as CSS `position`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub enum Position {
-    Relative(Rect),
-    Absolute(Rect),
+    /// Laid out with the other children and then moved by it's insets.
+    #[default]
+    Relative,
+    /// Taken out of the layout and placed by it's insets against the container.
+    Absolute,
+}
+
+/// Distance from each edge of the parent, set with `left`, `top`, `right` and `bottom`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Inset {
+    pub left: Option<usize>,
+    pub top: Option<usize>,
+    pub right: Option<usize>,
+    pub bottom: Option<usize>,
+    /// Percentage of the parent, set with `Unit::Percentage`.
+    pub relative_left: Option<f32>,
+    pub relative_top: Option<f32>,
+    pub relative_right: Option<f32>,
+    pub relative_bottom: Option<f32>,
+}
+
+impl Inset {
+    pub const fn new() -> Self {
+        Self {
+            left: None,
+            top: None,
+            right: None,
+            bottom: None,
+            relative_left: None,
+            relative_top: None,
+            relative_right: None,
+            relative_bottom: None,
+        }
+    }
+
+    /// Turn the percentages into pixels once the size of the parent is known.
+    pub fn resolve(mut self, parent: Rect) -> Self {
+        let percent = |p: Option<f32>, length: usize, inset: Option<usize>| match p {
+            Some(p) => Some(Unit::Percentage(p).resolve(length, 0)),
+            None => inset,
+        };
+        self.left = percent(self.relative_left, parent.width, self.left);
+        self.top = percent(self.relative_top, parent.height, self.top);
+        self.right = percent(self.relative_right, parent.width, self.right);
+        self.bottom = percent(self.relative_bottom, parent.height, self.bottom);
+        self
+    }
+
+    /// Place `area` against the edges of `parent`.
+    /// When both opposite insets are set the area is stretched between them.
+    /// An axis without any insets is left alone.
+    pub fn place(&self, area: &mut Rect, parent: Rect) {
+        let inset = self.resolve(parent);
+        match (inset.left, inset.right) {
+            (Some(left), Some(right)) => {
+                area.x = parent.x + left;
+                area.width = parent.width.saturating_sub(left + right);
+            }
+            (Some(left), None) => area.x = parent.x + left,
+            (None, Some(right)) => area.x = (parent.x + parent.width).saturating_sub(right + area.width),
+            (None, None) => {}
+        }
+        match (inset.top, inset.bottom) {
+            (Some(top), Some(bottom)) => {
+                area.y = parent.y + top;
+                area.height = parent.height.saturating_sub(top + bottom);
+            }
+            (Some(top), None) => area.y = parent.y + top,
+            (None, Some(bottom)) => area.y = (parent.y + parent.height).saturating_sub(bottom + area.height),
+            (None, None) => {}
+        }
+    }
+
+    /// Move `area` away from where it was laid out, `left` and `top` win over `right` and `bottom`.
+    /// Percentages need to be resolved first.
+    pub fn offset(&self, area: &mut Rect) {
+        match (self.left, self.right) {
+            (Some(left), _) => area.x += left,
+            (None, Some(right)) => area.x = area.x.saturating_sub(right),
+            (None, None) => {}
+        }
+        match (self.top, self.bottom) {
+            (Some(top), _) => area.y += top,
+            (None, Some(bottom)) => area.y = area.y.saturating_sub(bottom),
+            (None, None) => {}
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
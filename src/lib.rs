@@ -1,26 +1,36 @@
 #![allow(unused, static_mut_refs, incomplete_features)]
 #![feature(associated_type_defaults, specialization)]
 use mini::{error, info, profile, warn};
-use std::{any::Any, borrow::Cow, pin::Pin, sync::Arc};
+use std::{any::Any, borrow::Cow, pin::Pin, rc::Rc, sync::Arc};
 
 pub use core::ffi::c_void;
 
 pub mod atomic_float;
+pub mod drag;
 pub mod flex;
+pub mod focus;
+pub mod grid;
 pub mod input;
 pub mod layout;
 pub mod macros;
 pub mod platform;
 pub mod scaling;
+pub mod scroll;
+pub mod shortcut;
 pub mod style;
 pub mod widgets;
 
+pub use drag::*;
 pub use flex::*;
+pub use focus::*;
+pub use grid::*;
 pub use input::*;
 pub use layout::*;
 pub use macros::*;
 pub use platform::*;
 pub use scaling::*;
+pub use scroll::*;
+pub use shortcut::*;
 pub use style::*;
 pub use widgets::*;
 
@@ -45,7 +55,22 @@ pub struct Command {
 }
 
 impl Command {
+    /// Handlers are run straight away instead of being drawn, they're skipped when there isn't a context.
     fn queue(self) {
+        let ctx = unsafe { CTX.as_mut() };
+        match (&self.primative, ctx) {
+            (Primative::Handler(handler), Some(ctx)) => return handler.run(ctx, self.area),
+            (Primative::Handler(_), None) => return,
+            (Primative::Layer(layer), Some(ctx)) => {
+                let area = self.area;
+                let (x, y) = (
+                    area.x as isize - layer.offset.0 as isize,
+                    area.y as isize - layer.offset.1 as isize,
+                );
+                layer.run_handlers(ctx, x, y, area);
+            }
+            _ => {}
+        }
         unsafe {
             COMMAND_QUEUE.push(Command {
                 area: self.area,
@@ -60,12 +85,15 @@ pub enum Primative {
     /// (radius, color)
     Ellipse(usize, Color),
     RectangleOutline(Color),
-    /// (text, font_size, Color)
+    /// (text, font_size, Color, horizontal alignment, vertical alignment)
     /// This needs to include the desired font.
     /// Not sure how to do that yet.
     //TODO: Should font size be f32?
     //TODO: Could change text to Cow<'_, str>
-    Text(String, usize, Color),
+    Text(String, usize, Color, TextAlign, VerticalAlign),
+    /// (spans, line_height, alignment)
+    /// Wrapped to the width of the command area.
+    RichText(Vec<Span<'static>>, usize, TextAlign),
 
     // TODO: Now idea how to allow this properly.
     // CustomBoxed(Box<dyn FnOnce(&mut Context) -> ()>),
@@ -73,12 +101,17 @@ pub enum Primative {
     // CustomFn(fn(&mut Context) -> ()),
     // Custom(fn(&mut Context, Box<dyn std::any::Any>) -> (), Box<dyn DynClone>),
     #[cfg(feature = "image")]
-    ///(bitmap, x, y, width, height, format)
-    ImageUnsafe(&'static [u8], ImageFormat),
+    ImageUnsafe(&'static Image),
 
     #[cfg(feature = "svg")]
     SVGUnsafe(&'static resvg::tiny_skia::Pixmap),
 
+    /// Drawn offscreen and clipped to the command area, see `scroll!`.
+    Layer(Rc<Layer>),
+
+    /// Doesn't draw anything, runs the input behaviour of a widget inside it's final area.
+    Handler(Handler),
+
     Custom(fn(&mut Context, Rect) -> ()),
     CustomAny {
         data: Arc<dyn Any + Send + Sync>,
@@ -91,15 +124,23 @@ impl std::fmt::Debug for Primative {
         match self {
             Self::Ellipse(arg0, arg1) => f.debug_tuple("Ellipse").field(arg0).field(arg1).finish(),
             Self::RectangleOutline(arg0) => f.debug_tuple("RectangleOutline").field(arg0).finish(),
-            Self::Text(arg0, arg1, arg2) => f.debug_tuple("Text").field(arg0).field(arg1).field(arg2).finish(),
+            Self::Text(arg0, arg1, arg2, arg3, arg4) => f
+                .debug_tuple("Text")
+                .field(arg0)
+                .field(arg1)
+                .field(arg2)
+                .field(arg3)
+                .field(arg4)
+                .finish(),
+            Self::RichText(arg0, arg1, arg2) => f.debug_tuple("RichText").field(arg0).field(arg1).field(arg2).finish(),
             // Self::CustomBoxed(arg0) => f.debug_tuple("CustomBoxed").finish(),
             // Self::CustomFn(arg0) => f.debug_tuple("CustomFn").field(arg0).finish(),
             // Self::CustomAreaFn(arg0) => f.debug_tuple("CustomAreaFn").field(arg0).finish(),
             #[cfg(feature = "image")]
-            Self::ImageUnsafe(arg0, arg1) => f
+            Self::ImageUnsafe(arg0) => f
                 .debug_tuple("ImageUnsafe")
                 // .field(arg0)
-                .field(arg1)
+                .field(&arg0.format)
                 .finish(),
             _ => f.debug_tuple("Unknown").finish(),
         }
@@ -137,10 +178,13 @@ pub fn queue_custom(f: fn(&mut Context, Rect), area: Rect) {
     }
 }
 
+/// Widgets outside of a layout are placed against the window by their insets.
 pub fn draw_widget<W: Widget>(widget: W) {
+    let mut area = widget.area();
+    widget.item().inset.place(&mut area, viewport());
     unsafe {
         COMMAND_QUEUE.push(Command {
-            area: widget.area(),
+            area,
             primative: widget.primative(),
         });
     }
@@ -160,7 +204,7 @@ pub fn create_ctx(title: &str, width: usize, height: usize) -> &'static mut Cont
         #[cfg(target_os = "windows")]
         let window = create_window(title, 0, 0, width as i32, height as i32, WindowStyle::DEFAULT);
 
-        #[cfg(target_os = "macos")]
+        #[cfg(not(target_os = "windows"))]
         let window = Box::pin(Window::new(title, width, height));
 
         let mut context = Context::new(title, window);
@@ -190,35 +234,103 @@ pub fn create_ctx_ex(title: &str, window: Pin<Box<Window>>) -> &'static mut Cont
 pub struct Context {
     pub window: Pin<Box<Window>>,
     pub fill_color: Color,
+    pub text_rendering: TextRendering,
+    /// Cleared after every frame.
+    pub scroll_input: ScrollInput,
+    pub scroll: ScrollStates,
+    /// Left, right, middle, mouse 4 and mouse 5, see `click_state`.
+    pub clicks: [ClickState; 5],
+    pub hover: HoverState,
+    pub drag: DragState,
+    pub focus: FocusState,
+    pub shortcuts: Shortcuts,
 }
 
 impl Context {
     pub fn new(title: &str, mut window: Pin<Box<Window>>) -> Self {
-        //TODO: Remove me.
-        load_default_font();
         let fill_color = black();
         window.buffer.fill(fill_color.as_u32());
-        Self { window, fill_color }
+        Self {
+            window,
+            fill_color,
+            text_rendering: TextRendering::Grayscale,
+            scroll_input: ScrollInput::default(),
+            scroll: ScrollStates::default(),
+            clicks: [ClickState::default(); 5],
+            hover: HoverState::default(),
+            drag: DragState::default(),
+            focus: FocusState::default(),
+            shortcuts: Shortcuts::default(),
+        }
     }
 
-    // #[inline]
-    // pub const fn width(&self) -> ScaledUnit {
-    //     ScaledUnit::ViewportWidth(0)
-    // }
+    /// Runs when no focused widget handles the keys, returns the shortcuts it conflicts with.
+    pub fn shortcut(&mut self, shortcut: Shortcut, f: impl FnMut() + 'static) -> Vec<Shortcut> {
+        self.shortcuts.register(shortcut, f)
+    }
 
-    // #[inline]
-    // pub const fn height(&self) -> ScaledUnit {
-    //     ScaledUnit::ViewportHeight(0)
-    // }
+    /// Widgets with any behaviour cover the ones below them.
+    pub fn register_hover(&mut self, id: WidgetId, area: Rect) -> HoverId {
+        self.hover.register(id, area)
+    }
+
+    /// Returns if the widget is being dragged and if a drag ended on top of it.
+    pub fn register_drag(&mut self, hover: HoverId, area: Rect, draggable: bool) -> (bool, bool) {
+        let source = draggable && {
+            let index = self.drag.register(area);
+            self.drag.source == Some(index)
+        };
+        if source {
+            self.drag.source_area = Some(area);
+        }
+        let target = self.drag.phase == DragPhase::Ended && self.drag.target == Some(hover);
+        (source, target)
+    }
+
+    /// Returns if the widget has focus, clicking it moves the focus without showing the ring.
+    pub fn register_focus(&mut self, hover: HoverId, area: Rect, requested: bool) -> bool {
+        self.focus.register(hover);
+        let left = *click_state(self, MouseButton::Left);
+        if requested {
+            self.focus.focus(hover);
+        }
+        if left.down && left.press_position.intersects(area) && self.hover.hovered(self.window.mouse_position, hover) {
+            self.focus.focus(hover);
+            self.focus.visible = false;
+        }
+
+        let focused = self.focus.focused == Some(hover);
+        if focused {
+            self.focus.drawn();
+            if self.focus.visible {
+                self.focus.ring = Some(area);
+            }
+        }
+        focused
+    }
 
     #[inline]
     pub fn event(&mut self) -> Option<Event> {
-        self.window.event()
+        let event = self.window.event();
+        update_clicks(self);
+        if let Some(Event::Input(key, modifiers)) = &event {
+            self.scroll_input.record(key);
+            let chords = self.shortcuts.record(key, modifiers);
+            self.focus.record(key, modifiers, chords);
+        }
+        event
     }
 
     #[inline]
     pub fn event_blocking(&mut self) -> Option<Event> {
-        self.window.event_blocking()
+        let event = self.window.event_blocking();
+        update_clicks(self);
+        if let Some(Event::Input(key, modifiers)) = &event {
+            self.scroll_input.record(key);
+            let chords = self.shortcuts.record(key, modifiers);
+            self.focus.record(key, modifiers, chords);
+        }
+        event
     }
 
     //TODO: There is no support for depth.
@@ -226,45 +338,26 @@ impl Context {
         profile!();
 
         while let Some(cmd) = unsafe { COMMAND_QUEUE.pop() } {
-            let x = cmd.area.x as usize;
-            let y = cmd.area.y as usize;
-            let width = cmd.area.width as usize;
-            let height = cmd.area.height as usize;
-
-            match cmd.primative {
-                //This should idealy have a z index/depth parameter.
-                // Command::Rectangle(x, y, width, height, color) => {
-                //     self.draw_rectangle(x, y, width, height, color);
-                // }
-                Primative::Ellipse(radius, color) => {
-                    if radius == 0 {
-                        self.draw_rectangle(x, y, width, height, color);
-                    } else {
-                        self.draw_rectangle_rounded(x, y, width, height, color, radius);
-                    }
-                }
-                Primative::RectangleOutline(color) => {
-                    self.draw_rectangle_outline(x, y, width, height, color);
-                }
-                Primative::Text(text, font_size, color) => {
-                    //TODO: Specify the font with a font database and font ID.
-                    let font = default_font().unwrap();
-                    self.draw_text(&text, &font, cmd.area.x, cmd.area.y, font_size, 0, color);
-                }
-                // Primative::CustomBoxed(f) => f(self),
-                // Primative::Custom(f, data) => f(self, data),
-                #[cfg(feature = "image")]
-                Primative::ImageUnsafe(bitmap, image_format) => {
-                    self.draw_image(x, y, width, height, bitmap, image_format);
-                }
-                #[cfg(feature = "svg")]
-                Primative::SVGUnsafe(pixmap) => {
-                    self.draw_svg(x, y, pixmap, false);
-                }
-                Primative::CustomAny { data, f } => f(self, cmd.area, &*data),
-                Primative::Custom(f) => f(self, cmd.area),
-            }
+            self.draw_command(cmd);
+        }
+
+        if let Some(ring) = self.focus.ring_command() {
+            self.draw_command(ring);
+        }
+        if let Some(preview) = self.drag.preview.take() {
+            self.draw_command(preview);
+        }
+
+        self.scroll_input = ScrollInput::default();
+        for clicks in &mut self.clicks {
+            clicks.next_frame();
         }
+        self.hover.next_frame(self.window.mouse_position);
+        self.shortcuts.run(&mut self.focus.keys);
+        self.shortcuts.next_frame();
+        self.focus.next_frame();
+        self.drag
+            .next_frame(self.window.mouse_position, self.window.left_mouse.pressed, &self.hover);
 
         self.window.draw();
         //Draw the UI on top of the background not the other way round!
@@ -279,6 +372,57 @@ impl Context {
         self.window.vsync();
     }
 
+    pub fn draw_command(&mut self, cmd: Command) {
+        let x = cmd.area.x;
+        let y = cmd.area.y;
+        let width = cmd.area.width;
+        let height = cmd.area.height;
+
+        match cmd.primative {
+            //This should idealy have a z index/depth parameter.
+            // Command::Rectangle(x, y, width, height, color) => {
+            //     self.draw_rectangle(x, y, width, height, color);
+            // }
+            Primative::Ellipse(radius, color) => {
+                if radius == 0 {
+                    self.draw_rectangle(x, y, width, height, color);
+                } else {
+                    self.draw_rectangle_rounded(x, y, width, height, color, radius);
+                }
+            }
+            Primative::RectangleOutline(color) => {
+                self.draw_rectangle_outline(x, y, width, height, color);
+            }
+            Primative::Text(text, font_size, color, align, vertical_align) => {
+                //TODO: Specify the font with a font database and font ID.
+                let font = default_font().unwrap();
+                let style = TextStyle {
+                    font_size,
+                    line_height: 0,
+                    color,
+                    align,
+                    vertical_align,
+                };
+                self.draw_text_aligned(&text, font, cmd.area, style);
+            }
+            Primative::RichText(spans, line_height, align) => {
+                self.draw_rich_text(&spans, cmd.area, line_height, align);
+            }
+            // Primative::CustomBoxed(f) => f(self),
+            // Primative::Custom(f, data) => f(self, data),
+            #[cfg(feature = "image")]
+            Primative::ImageUnsafe(image) => self.draw_image(cmd.area, image),
+            #[cfg(feature = "svg")]
+            Primative::SVGUnsafe(pixmap) => {
+                self.draw_svg(x, y, pixmap, false);
+            }
+            Primative::Layer(layer) => self.draw_layer(cmd.area, &layer),
+            Primative::Handler(handler) => handler.run(self, cmd.area),
+            Primative::CustomAny { data, f } => f(self, cmd.area, &*data),
+            Primative::Custom(f) => f(self, cmd.area),
+        }
+    }
+
     pub fn get_pixel(&mut self, x: usize, y: usize) -> Option<&mut u32> {
         let pos = x + (self.window.width() * y);
         self.window.buffer.get_mut(pos)
@@ -289,6 +433,11 @@ impl Context {
         self.window.buffer.fill(self.fill_color.as_u32());
     }
 
+    /// Subpixel rendering only looks correct on LCD panels with a matching subpixel order.
+    pub fn set_text_rendering(&mut self, text_rendering: TextRendering) {
+        self.text_rendering = text_rendering;
+    }
+
     #[inline]
     #[track_caller]
     pub fn draw_pixel(&mut self, x: usize, y: usize, color: Color) {
@@ -315,19 +464,19 @@ impl Context {
         border_color: Color,
         radius: usize,
     ) where
-        X: Into<GenericUnit>,
-        Y: Into<GenericUnit>,
-        WIDTH: Into<GenericUnit>,
-        HEIGHT: Into<GenericUnit>,
+        X: Into<Unit>,
+        Y: Into<Unit>,
+        WIDTH: Into<Unit>,
+        HEIGHT: Into<Unit>,
     {
         let viewport_width = self.window.width();
-        let viewport_height = self.window.area.height;
+        let viewport_height = self.window.height();
         let scale = self.window.display_scale();
 
-        let x = scale_temp(x.into(), self.window.area, scale);
-        let y = scale_temp(y.into(), self.window.area, scale);
-        let width = scale_temp(width.into(), self.window.area, scale);
-        let height = scale_temp(height.into(), self.window.area, scale);
+        let x = scale_unit(x.into(), viewport_width, scale);
+        let y = scale_unit(y.into(), viewport_height, scale);
+        let width = scale_unit(width.into(), viewport_width, scale);
+        let height = scale_unit(height.into(), viewport_height, scale);
 
         //Draw the rectangle border.
         if border != 0 {
@@ -617,11 +766,6 @@ impl Context {
         }
     }
 
-    //TODO: Allow the drawing text over multiple lines. Maybe draw text should return the y pos?
-    //or maybe the buffer should just include all the text related code and the metrics should be static.
-
-    //TODO: If the text is longer than canvas width it needs to be clipped.
-    //Currently it circles around and starts drawing from the front again.
     pub fn draw_text(
         &mut self,
         text: &str,
@@ -633,113 +777,318 @@ impl Context {
         line_height: usize,
         color: Color,
     ) {
+        let style = TextStyle {
+            font_size,
+            line_height,
+            color,
+            align: TextAlign::Left,
+            vertical_align: VerticalAlign::Top,
+        };
+        self.draw_text_aligned(text, font, Rect::new(x, y, 0, 0), style);
+    }
+
+    //TODO: If the text is longer than canvas width it needs to be clipped.
+    /// Lines are placed using the ascent and descent of the font.
+    /// The glyphs sit on the baseline instead of being offset by the font size.
+    pub fn draw_text_aligned(&mut self, text: &str, font: &fontdue::Font, area: Rect, style: TextStyle) {
+        let TextStyle {
+            font_size,
+            line_height,
+            color,
+            align,
+            vertical_align,
+        } = style;
         if text.is_empty() || font_size == 0 {
             return;
         }
 
-        let viewport_width = self.window.width();
-        let viewport_height = self.window.area.height;
-
-        let x = scale(x, self.window.display_scale);
-        let y = scale(y, self.window.display_scale);
-        let font_size = scale(font_size, self.window.display_scale);
-        let line_height = scale(line_height, self.window.display_scale);
+        let scale_factor = self.window.display_scale;
+        let x = scale(area.x, scale_factor);
+        let y = scale(area.y, scale_factor);
+        let width = scale(area.width, scale_factor);
+        let height = scale(area.height, scale_factor);
+        let font_size = scale(font_size, scale_factor);
+        let line_height = scale(line_height, scale_factor);
+
+        let (ascent, _) = line_metrics(font, font_size as f32);
+        let line = line_box(font, font_size as f32) + line_height;
+        let (_, block_height) = text_size(font, text, font_size, line_height);
+        let line_count = text.lines().count();
+
+        let mut line_y = y + align_block(vertical_align, height, block_height);
+
+        for (i, l) in text.lines().enumerate() {
+            let last_line = i + 1 == line_count;
+            let direction = paragraph_direction(l);
+            let spaces = l.chars().filter(|c| *c == ' ').count();
+            let (start, space) = align_line(
+                align.resolve(direction, last_line),
+                width,
+                line_width(font, l, font_size as f32),
+                spaces,
+                last_line,
+            );
+            let baseline = line_y as i32 + ascent.ceil() as i32;
+            let mut glyph_x = x as f32 + start;
+
+            for (_, char, _) in visual_chars(l, direction) {
+                glyph_x += self.draw_char(font, char, font_size as f32, glyph_x, baseline, color);
+                if char == ' ' {
+                    glyph_x += space;
+                }
 
-        let mut area = Rect::new(x, y, 0, 0);
-        let mut y: usize = area.y.try_into().unwrap();
-        let x = area.x as usize;
+                //Check if the glyph position is off the screen.
+                if glyph_x as usize >= self.window.width() {
+                    break;
+                }
+            }
 
-        let mut max_x = 0;
-        let mut max_y = 0;
+            line_y += line;
+        }
+    }
 
-        let r = color.r();
-        let g = color.g();
-        let b = color.b();
+    /// Draw a paragraph of spans, wrapping at the width of the area.
+    /// Highlights are drawn first, then the glyphs and finally the decorations.
+    pub fn draw_rich_text(&mut self, spans: &[Span], area: Rect, line_height: usize, align: TextAlign) {
+        let scale_factor = self.window.display_scale;
+        let x = scale(area.x, scale_factor);
+        let y = scale(area.y, scale_factor);
+        let width = scale(area.width, scale_factor);
+        let line_height = scale(line_height, scale_factor);
+
+        let spans: Vec<Span> = spans
+            .iter()
+            .map(|span| span.clone().font_size(scale(span.font_size, scale_factor)))
+            .collect();
+
+        let lines = layout_spans(&spans, if width == 0 { None } else { Some(width) }, line_height);
+        let line_count = lines.len();
+
+        for (i, line) in lines.iter().enumerate() {
+            let spaces = line
+                .fragments
+                .iter()
+                .map(|f| spans[f.run].text[f.start..f.end].matches(' ').count())
+                .sum();
+            let last_line = i + 1 == line_count;
+            let direction = line.direction;
+            let (start, space) = align_line(
+                align.resolve(direction, last_line),
+                width,
+                line.width,
+                spaces,
+                last_line,
+            );
+            let top = y + line.y;
+            let baseline = (y + line.baseline()) as i32;
+            let mut pen = x as f32 + start;
+
+            //Spans are reordered as a whole, the characters inside each span are reordered separately.
+            let fragments: Vec<&Fragment> = match direction {
+                TextDirection::LeftToRight => line.fragments.iter().collect(),
+                TextDirection::RightToLeft => line.fragments.iter().rev().collect(),
+            };
+
+            for fragment in fragments {
+                let span = &spans[fragment.run];
+                let text = &span.text[fragment.start..fragment.end];
+                let font = span.font_or_default();
+                let size = span.font_size as f32;
+                let fragment_x = pen;
+                let fragment_width = fragment.width + space * text.matches(' ').count() as f32;
+
+                if let Some(highlight) = span.highlight {
+                    let width = fragment_width.ceil() as usize;
+                    self.draw_rectangle(fragment_x as usize, top, width, line.height(), highlight);
+                }
 
-        'line: for line in text.lines() {
-            let mut glyph_x = x;
+                for (_, char, _) in visual_chars(text, direction) {
+                    pen += self.draw_char(font, char, size, pen, baseline, span.color);
+                    if char == ' ' {
+                        pen += space;
+                    }
+                }
 
-            'char: for char in line.chars() {
-                let (metrics, bitmap) = font.rasterize(char, font_size as f32);
+                //Don't decorate the space hanging off the end of the line.
+                let decoration_width = if std::ptr::eq(fragment, line.fragments.last().unwrap()) {
+                    line_width(font, text.trim_end_matches(' '), size)
+                } else {
+                    fragment_width
+                }
+                .ceil() as usize;
+                let thickness = (size / 14.0).ceil().max(1.0) as usize;
+
+                if span.underline {
+                    let underline_y = baseline as usize + thickness;
+                    self.draw_rectangle(
+                        fragment_x as usize,
+                        underline_y,
+                        decoration_width,
+                        thickness,
+                        span.color,
+                    );
+                }
 
-                let glyph_y = y as f32 - (metrics.height as f32 - metrics.advance_height) - metrics.ymin as f32;
+                if span.strikethrough {
+                    //Roughly the middle of a lower case letter.
+                    let (ascent, _) = line_metrics(font, size);
+                    let strike_y = (baseline as f32 - ascent * 0.3).round() as usize;
+                    self.draw_rectangle(fragment_x as usize, strike_y, decoration_width, thickness, span.color);
+                }
+            }
+        }
+    }
 
-                'y: for y in 0..metrics.height {
-                    'x: for x in 0..metrics.width {
-                        //Text doesn't fit on the screen.
-                        if (x + glyph_x) >= self.window.width() {
-                            continue;
-                        }
+    /// Rasterize a character sitting on the baseline using the current `TextRendering`.
+    /// Returns the advance width of the glyph.
+    pub fn draw_char(
+        &mut self,
+        font: &fontdue::Font,
+        char: char,
+        font_size: f32,
+        x: f32,
+        baseline: i32,
+        color: Color,
+    ) -> f32 {
+        //Emoji and anything else the font is missing.
+        if let Some(glyph) = fallback_glyph(font, char, font_size, color) {
+            let metrics = &glyph.metrics;
+            let y = baseline - metrics.height as i32 - metrics.ymin;
+            self.draw_rgba(x as i32 + metrics.xmin, y, metrics.width, metrics.height, &glyph.rgba);
+            return metrics.advance_width;
+        }
 
-                        //TODO: Metrics.bounds determines the bounding are of the glyph.
-                        //Currently the whole bitmap bounding box is drawn.
-                        let alpha = bitmap[x + y * metrics.width];
-                        if alpha == 0 {
-                            continue;
-                        }
+        match self.text_rendering {
+            TextRendering::Grayscale => {
+                let (metrics, bitmap) = font.rasterize(char, font_size);
+                //ymin is the distance from the baseline to the bottom of the glyph.
+                let y = baseline - metrics.height as i32 - metrics.ymin;
+                self.draw_glyph(x as i32 + metrics.xmin, y, &metrics, &bitmap, color);
+                metrics.advance_width
+            }
+            TextRendering::Subpixel | TextRendering::SubpixelBgr => {
+                let (metrics, bitmap) = font.rasterize_subpixel(char, font_size);
+                let y = baseline - metrics.height as i32 - metrics.ymin;
+                self.draw_glyph_lcd(x as i32 + metrics.xmin, y, &metrics, &bitmap, color);
+                metrics.advance_width
+            }
+        }
+    }
 
-                        //Should the text really be offset by the font size?
-                        //This allows the user to draw text at (0, 0).
-                        let offset = font_size as f32 + glyph_y + y as f32;
+    /// Blend a straight alpha RGBA bitmap over the framebuffer.
+    pub fn draw_rgba(&mut self, x: i32, y: i32, width: usize, height: usize, rgba: &[u8]) {
+        let viewport_width = self.window.width() as i32;
+        let viewport_height = self.window.area.height as i32;
 
-                        //We can't render off of the screen, mkay?
-                        if offset < 0.0 {
-                            continue;
-                        }
+        for gy in 0..height {
+            let py = y + gy as i32;
+            if py < 0 || py >= viewport_height {
+                continue;
+            }
 
-                        if max_x < x + glyph_x {
-                            max_x = x + glyph_x;
-                        }
+            for gx in 0..width {
+                let px = x + gx as i32;
+                if px < 0 || px >= viewport_width {
+                    continue;
+                }
 
-                        if max_y < offset as usize {
-                            max_y = offset as usize;
-                        }
+                let j = (gx + gy * width) * 4;
+                let alpha = rgba[j + 3];
+                if alpha == 0 {
+                    continue;
+                }
 
-                        let i = x + glyph_x + self.window.width() * offset as usize;
+                let i = px as usize + self.window.width() * py as usize;
+                if let Some(pixel) = self.window.buffer.get_mut(i) {
+                    let bg = Color(*pixel);
+                    let r = blend(rgba[j], alpha, bg.r(), 255 - alpha);
+                    let g = blend(rgba[j + 1], alpha, bg.g(), 255 - alpha);
+                    let b = blend(rgba[j + 2], alpha, bg.b(), 255 - alpha);
+                    *pixel = rgb(r, g, b).as_u32();
+                }
+            }
+        }
+    }
 
-                        if i >= self.window.buffer.len() {
-                            break 'x;
-                        }
+    /// Filter a subpixel bitmap from fontdue and blend it over the framebuffer.
+    /// Each channel has its own coverage, so each channel is blended separately.
+    pub fn draw_glyph_lcd(&mut self, x: i32, y: i32, metrics: &fontdue::Metrics, bitmap: &[u8], color: Color) {
+        let (width, coverage) = lcd_filter(metrics.width, metrics.height, bitmap);
+        let height = metrics.height;
+        let bgr = self.text_rendering == TextRendering::SubpixelBgr;
+        //The filter spreads the glyph one pixel to the left.
+        let x = x - 1;
+        let viewport_width = self.window.width() as i32;
+        let viewport_height = self.window.area.height as i32;
+
+        for gy in 0..height {
+            let py = y + gy as i32;
+            if py < 0 || py >= viewport_height {
+                continue;
+            }
 
-                        let bg = Color(self.window.buffer[i]);
+            for gx in 0..width {
+                let px = x + gx as i32;
+                if px < 0 || px >= viewport_width {
+                    continue;
+                }
 
-                        let r = blend(r, alpha, bg.r(), 255 - alpha);
-                        let g = blend(g, alpha, bg.g(), 255 - alpha);
-                        let b = blend(b, alpha, bg.b(), 255 - alpha);
+                let j = (gx + gy * width) * 3;
+                let (ar, ag, ab) = if bgr {
+                    (coverage[j + 2], coverage[j + 1], coverage[j])
+                } else {
+                    (coverage[j], coverage[j + 1], coverage[j + 2])
+                };
 
-                        if let Some(px) = self.window.buffer.get_mut(i) {
-                            *px = rgb(r, g, b).as_u32();
-                        }
+                if ar == 0 && ag == 0 && ab == 0 {
+                    continue;
+                }
 
-                        // self.window.buffer[i] = rgb(r, g, b).as_u32();
-                    }
+                let i = px as usize + self.window.width() * py as usize;
+                if let Some(pixel) = self.window.buffer.get_mut(i) {
+                    let bg = Color(*pixel);
+                    let r = blend(color.r(), ar, bg.r(), 255 - ar);
+                    let g = blend(color.g(), ag, bg.g(), 255 - ag);
+                    let b = blend(color.b(), ab, bg.b(), 255 - ab);
+                    *pixel = rgb(r, g, b).as_u32();
                 }
+            }
+        }
+    }
 
-                glyph_x += metrics.advance_width as usize;
+    /// Blend a coverage bitmap from fontdue over the framebuffer.
+    /// Anything outside of the window is clipped.
+    pub fn draw_glyph(&mut self, x: i32, y: i32, metrics: &fontdue::Metrics, bitmap: &[u8], color: Color) {
+        let viewport_width = self.window.width() as i32;
+        let viewport_height = self.window.area.height as i32;
 
-                //Check if the glyph position is off the screen.
-                if glyph_x >= self.window.width() {
-                    break 'line;
-                }
+        for gy in 0..metrics.height {
+            let py = y + gy as i32;
+            if py < 0 || py >= viewport_height {
+                continue;
             }
 
-            //CSS is probably line height * font size.
-            //1.2 is the default line height
-            //I'm guessing 1.0 is probably just adding the font size.
-            y += font_size + line_height;
-        }
+            for gx in 0..metrics.width {
+                let px = x + gx as i32;
+                if px < 0 || px >= viewport_width {
+                    continue;
+                }
 
-        //Not sure why these are one off.
-        area.height = max_y + 1 - area.y;
-        area.width = max_x + 1 - area.x;
+                let alpha = bitmap[gx + gy * metrics.width];
+                if alpha == 0 {
+                    continue;
+                }
 
-        // self.draw_rectangle_outline(
-        //     area.x as usize,
-        //     area.y as usize,
-        //     area.width as usize,
-        //     area.height as usize,
-        //     Color::RED,
-        // );
+                let i = px as usize + self.window.width() * py as usize;
+                if let Some(pixel) = self.window.buffer.get_mut(i) {
+                    let bg = Color(*pixel);
+                    let r = blend(color.r(), alpha, bg.r(), 255 - alpha);
+                    let g = blend(color.g(), alpha, bg.g(), 255 - alpha);
+                    let b = blend(color.b(), alpha, bg.b(), 255 - alpha);
+                    *pixel = rgb(r, g, b).as_u32();
+                }
+            }
+        }
     }
 
     #[cfg(target_os = "windows")]
@@ -875,41 +1224,98 @@ impl Context {
         }
     }
 
+    /// Draw an image scaled to `area` with nearest neighbour sampling.
+    /// The alpha channel of PNGs is blended with the background.
     #[cfg(feature = "image")]
-    pub fn draw_image(&mut self, x: usize, y: usize, width: usize, height: usize, bitmap: &[u8], format: ImageFormat) {
-        let start_x = x;
-        let start_y = y;
-        let viewport_width = self.window.width();
-        let buffer = &mut self.window.buffer;
-        let len = buffer.len();
+    pub fn draw_image(&mut self, area: Rect, image: &Image) {
+        let (bitmap_width, bitmap_height) = image.size;
+        if bitmap_width == 0 || bitmap_height == 0 {
+            return;
+        }
 
         //4 bytes RGBA, 3 bytes RGB
-        let chunk_size = if format == ImageFormat::PNG { 4 } else { 3 };
-
-        let mut x = 0;
-        let mut y = 0;
-
-        for pixel in bitmap.chunks(chunk_size) {
-            let pos = (y + start_y) * viewport_width + (x + start_x);
+        let chunk_size = if image.format == ImageFormat::PNG { 4 } else { 3 };
+
+        for py in 0..area.height {
+            let sy = py * bitmap_height / area.height;
+            for px in 0..area.width {
+                let sx = px * bitmap_width / area.width;
+                let i = (sy * bitmap_width + sx) * chunk_size;
+                let Some(pixel) = image.bitmap.get(i..i + chunk_size) else {
+                    continue;
+                };
+                let alpha = if chunk_size == 4 { pixel[3] } else { 255 };
+                if alpha == 0 {
+                    continue;
+                }
 
-            if pos >= len {
-                break;
+                let i = (area.x + px) + self.window.width() * (area.y + py);
+                if let Some(dst) = self.window.buffer.get_mut(i) {
+                    let bg = Color(*dst);
+                    let r = blend(pixel[0], alpha, bg.r(), 255 - alpha);
+                    let g = blend(pixel[1], alpha, bg.g(), 255 - alpha);
+                    let b = blend(pixel[2], alpha, bg.b(), 255 - alpha);
+                    *dst = rgb(r, g, b).as_u32();
+                }
             }
+        }
+    }
 
-            let r = pixel[0];
-            let g = pixel[1];
-            let b = pixel[2];
-            // let a = pixel[3];
-            let color = rgb(r, g, b);
+    /// Draw the commands of a layer offscreen and copy the visible part into `area`.
+    /// Only commands that can be seen are drawn, the offscreen buffer is just large enough to fit them.
+    pub fn draw_layer(&mut self, area: Rect, layer: &Layer) {
+        let (offset_x, offset_y) = layer.offset;
+        let visible = Rect::new(offset_x, offset_y, area.width, area.height);
+        let commands: Vec<&Command> = layer
+            .commands
+            .iter()
+            .filter(|c| c.area.intersects(visible) && !matches!(c.primative, Primative::Handler(_)))
+            .collect();
+
+        //Commands that are cut off are still drawn from their top left corner.
+        let left = commands.iter().map(|c| c.area.x).fold(offset_x, usize::min);
+        let top = commands.iter().map(|c| c.area.y).fold(offset_y, usize::min);
+        let right = commands
+            .iter()
+            .map(|c| c.area.right())
+            .fold(visible.right(), usize::max);
+        let bottom = commands
+            .iter()
+            .map(|c| c.area.bottom())
+            .fold(visible.bottom(), usize::max);
+        let target = Rect::new(0, 0, right - left, bottom - top);
+        let (view_x, view_y) = (offset_x - left, offset_y - top);
+
+        let (window_width, window_height) = (self.window.width(), self.window.height());
+        let width = area.width.min(window_width.saturating_sub(area.x));
+        let height = area.height.min(window_height.saturating_sub(area.y));
+
+        //Start with whatever is behind the layer so text blends with it.
+        let mut buffer = vec![self.fill_color.as_u32(); target.width * target.height];
+        for y in 0..height {
+            let from = (area.y + y) * window_width + area.x;
+            let to = (view_y + y) * target.width + view_x;
+            buffer[to..to + width].copy_from_slice(&self.window.buffer[from..from + width]);
+        }
 
-            buffer[pos] = color.as_u32();
+        std::mem::swap(&mut self.window.buffer, &mut buffer);
+        let window_area = std::mem::replace(&mut self.window.area, target);
+        for cmd in commands {
+            let mut area = cmd.area;
+            area.x -= left;
+            area.y -= top;
+            self.draw_command(Command {
+                area,
+                primative: cmd.primative.clone(),
+            });
+        }
+        self.window.area = window_area;
+        std::mem::swap(&mut self.window.buffer, &mut buffer);
 
-            x += 1;
-            if x >= width {
-                y += 1;
-                x = 0;
-                continue;
-            }
+        for y in 0..height {
+            let from = (view_y + y) * target.width + view_x;
+            let to = (area.y + y) * window_width + area.x;
+            self.window.buffer[to..to + width].copy_from_slice(&buffer[from..from + width]);
         }
     }
 
@@ -4,10 +4,10 @@ pub use window::*;
 
 // Rect, Window, Event
 
-#[cfg(target_os = "macos")]
+#[cfg(not(target_os = "windows"))]
 pub use macos::*;
 
-#[cfg(target_os = "macos")]
+#[cfg(not(target_os = "windows"))]
 pub mod macos {
     use super::*;
     use std::pin::Pin;
@@ -41,7 +41,11 @@ pub mod macos {
             self.drawn = false;
 
             let (x, y) = self.minifb.get_mouse_pos(MouseMode::Pass).unwrap();
-            self.mouse_position = Rect::new(x as usize, y as usize, 1, 1);
+            let mouse_position = Rect::new(x as usize, y as usize, 1, 1);
+            if mouse_position != self.mouse_position {
+                self.event_cache.push(Event::MouseMove(x as i32, y as i32));
+            }
+            self.mouse_position = mouse_position;
 
             if self.minifb.get_mouse_down(minifb::MouseButton::Left) {
                 self.left_mouse.pressed(self.mouse_position);
@@ -61,8 +65,27 @@ pub mod macos {
                 self.right_mouse.released(self.mouse_position);
             }
 
+            if let Some((_, y)) = self.minifb.get_scroll_wheel() {
+                if y > 0.0 {
+                    self.event_cache.push(Event::Input(Key::ScrollUp, Modifiers::default()));
+                } else if y < 0.0 {
+                    self.event_cache
+                        .push(Event::Input(Key::ScrollDown, Modifiers::default()));
+                }
+            }
+
+            //minifb doesn't give the modifiers with each key, so they're read from the key state.
+            let down = |left, right| self.minifb.is_key_down(left) || self.minifb.is_key_down(right);
+            let modifiers = Modifiers {
+                control: down(minifb::Key::LeftCtrl, minifb::Key::RightCtrl),
+                shift: down(minifb::Key::LeftShift, minifb::Key::RightShift),
+                alt: down(minifb::Key::LeftAlt, minifb::Key::RightAlt),
+                win: down(minifb::Key::LeftSuper, minifb::Key::RightSuper),
+            };
+
             self.event_cache.extend(convert_minifb_key_to_softui(
                 &self.minifb.get_keys_pressed(KeyRepeat::No),
+                modifiers,
             ));
 
             return self.event_cache.pop();
@@ -101,7 +124,7 @@ pub mod macos {
         }
     }
 
-    pub fn convert_minifb_key_to_softui(keys: &[minifb::Key]) -> Vec<Event> {
+    pub fn convert_minifb_key_to_softui(keys: &[minifb::Key], modifiers: Modifiers) -> Vec<Event> {
         let mut events = Vec::new();
         for key in keys {
             let key = match key {
@@ -182,7 +205,7 @@ pub mod macos {
                 _ => Key::Unknown(0),
             };
 
-            events.push(Event::Input(key, Modifiers::default()));
+            events.push(Event::Input(key, modifiers));
         }
         events
     }
@@ -207,7 +230,7 @@ pub mod macos {
         Input(Key, Modifiers),
     }
 
-    #[derive(Default, Debug, PartialEq)]
+    #[derive(Default, Debug, Clone, Copy, PartialEq)]
     pub struct Modifiers {
         pub control: bool,
         pub shift: bool,
@@ -215,7 +238,7 @@ pub mod macos {
         pub win: bool,
     }
 
-    #[derive(Debug, PartialEq)]
+    #[derive(Debug, Clone, Copy, PartialEq)]
     pub enum Key {
         Char(char),
         Function(u8),
@@ -0,0 +1,460 @@
+//! A container that lays it's content out at full size and only shows part of it.
+//!
+//! ```rs
+//! scroll!(text("One"), text("Two"), text("Three"))
+//!     .gap(8)
+//!     .h(200);
+//! ```
+//!
+//! The content is drawn offscreen and copied into the view, so nothing is drawn outside of it.
+//! The scroll position is kept between frames, one for every `scroll!` in the source code.
+//! Use `.id()` when the same `scroll!` is used more than once, like inside of a loop.
+use crate::*;
+use std::{panic::Location, rc::Rc};
+
+/// Width of the scrollbars.
+pub const SCROLLBAR_SIZE: usize = 8;
+
+/// Scrollbar thumbs never get smaller than this.
+pub const MIN_THUMB: usize = 24;
+
+/// How much of the remaining distance is covered each frame when scrolling is smooth.
+pub const SMOOTHING: f32 = 0.25;
+
+/// Scrolling input received this frame, it's cleared after every frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ScrollInput {
+    /// Mouse wheel notches, positive scrolls down.
+    pub lines: f32,
+    /// Page up and page down, positive scrolls down.
+    pub pages: f32,
+    pub home: bool,
+    pub end: bool,
+}
+
+impl ScrollInput {
+    pub fn record(&mut self, key: &Key) {
+        match key {
+            Key::ScrollUp => self.lines -= 1.0,
+            Key::ScrollDown => self.lines += 1.0,
+            Key::PageUp => self.pages -= 1.0,
+            Key::PageDown => self.pages += 1.0,
+            Key::Home => self.home = true,
+            Key::End => self.end = true,
+            _ => {}
+        }
+    }
+}
+
+/// The position of a `scroll!`, `[horizontal, vertical]`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ScrollState {
+    /// What's drawn this frame, eases towards `target` when scrolling is smooth.
+    pub offset: [f32; 2],
+    pub target: [f32; 2],
+    /// (axis, mouse position, target) when a scrollbar thumb was grabbed.
+    pub drag: Option<(usize, usize, f32)>,
+    /// The left mouse button was down last frame.
+    pub held: bool,
+}
+
+impl ScrollState {
+    /// Keep the target inside the content and move the offset towards it.
+    pub fn update(&mut self, max: [f32; 2], smooth: bool) {
+        for (axis, max) in max.into_iter().enumerate() {
+            self.target[axis] = self.target[axis].clamp(0.0, max);
+            let distance = self.target[axis] - self.offset[axis];
+            self.offset[axis] = if smooth && distance.abs() > 0.5 && self.drag.is_none() {
+                self.offset[axis] + distance * SMOOTHING
+            } else {
+                self.target[axis]
+            };
+        }
+    }
+}
+
+/// (file, line, column, id)
+pub type ScrollId = (&'static str, u32, u32, usize);
+
+/// The position of every `scroll!` that has been drawn, kept between frames.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ScrollStates {
+    pub states: Vec<(ScrollId, ScrollState)>,
+}
+
+impl ScrollStates {
+    /// The state of a `scroll!`, created the first time it's used.
+    pub fn get_mut(&mut self, id: ScrollId) -> &mut ScrollState {
+        match self.states.iter().position(|(i, _)| *i == id) {
+            Some(i) => &mut self.states[i].1,
+            None => {
+                self.states.push((id, ScrollState::default()));
+                &mut self.states.last_mut().unwrap().1
+            }
+        }
+    }
+}
+
+/// (position, length) of the thumb along a scrollbar `track` pixels long.
+/// The thumb fills the track when there's nothing to scroll.
+pub fn thumb(track: usize, view: usize, content: usize, offset: f32) -> (usize, usize) {
+    if content <= view {
+        return (0, track);
+    }
+    let length = (track * view / content).max(MIN_THUMB).min(track);
+    let max_offset = (content - view) as f32;
+    let position = ((track - length) as f32 * (offset / max_offset).clamp(0.0, 1.0)).round() as usize;
+    (position, length)
+}
+
+/// The scrollbar along the bottom of the view for axis `0` and the right for axis `1`.
+fn track(view: Rect, axis: usize) -> Rect {
+    if axis == 0 {
+        Rect::new(
+            view.x,
+            view.bottom().saturating_sub(SCROLLBAR_SIZE),
+            view.width,
+            SCROLLBAR_SIZE,
+        )
+    } else {
+        Rect::new(
+            view.right().saturating_sub(SCROLLBAR_SIZE),
+            view.y,
+            SCROLLBAR_SIZE,
+            view.height,
+        )
+    }
+}
+
+fn thumb_rect(view: Rect, axis: usize, content: [usize; 2], offset: f32) -> Rect {
+    let track = track(view, axis);
+    if axis == 0 {
+        let (position, length) = thumb(track.width, view.width, content[0], offset);
+        Rect::new(track.x + position, track.y, length, track.height)
+    } else {
+        let (position, length) = thumb(track.height, view.height, content[1], offset);
+        Rect::new(track.x, track.y + position, track.width, length)
+    }
+}
+
+/// Commands that are drawn offscreen, moved up and left by `offset` and clipped to the command area.
+#[derive(Debug)]
+pub struct Layer {
+    pub commands: Vec<Command>,
+    pub offset: (usize, usize),
+}
+
+impl Layer {
+    /// Handlers only get the part of the widget that can be seen, in window coordinates.
+    /// `(x, y)` is where the top left of the content would be in the window.
+    pub fn run_handlers(&self, ctx: &mut Context, x: isize, y: isize, clip: Rect) {
+        for cmd in &self.commands {
+            let left = (x + cmd.area.x as isize).max(clip.x as isize);
+            let top = (y + cmd.area.y as isize).max(clip.y as isize);
+            let right = (x + cmd.area.right() as isize).min(clip.right() as isize);
+            let bottom = (y + cmd.area.bottom() as isize).min(clip.bottom() as isize);
+            if right <= left || bottom <= top {
+                continue;
+            }
+            let area = Rect::new(
+                left as usize,
+                top as usize,
+                (right - left) as usize,
+                (bottom - top) as usize,
+            );
+
+            match &cmd.primative {
+                Primative::Handler(handler) => handler.run(ctx, area),
+                Primative::Layer(layer) => layer.run_handlers(
+                    ctx,
+                    x + cmd.area.x as isize - layer.offset.0 as isize,
+                    y + cmd.area.y as isize - layer.offset.1 as isize,
+                    area,
+                ),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! scroll {
+    ($($widget:expr),* $(,)?) => {
+        $crate::DeferScroll::new($crate::v!($($widget),*))
+    };
+}
+
+pub struct DeferScroll<F: FnMut(Padding, usize) -> Container> {
+    pub content: DeferContainer<F>,
+    pub id: ScrollId,
+    pub x: usize,
+    pub y: usize,
+    /// The size of the content is used when `None`.
+    /// The height is limited to the bottom of the window.
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub smooth: bool,
+    pub bg: Option<Color>,
+    pub scrollbar: Color,
+    /// Set when the scroll is inside of another layout, which draws it instead.
+    pub nested: bool,
+}
+
+impl<F: FnMut(Padding, usize) -> Container> Drop for DeferScroll<F> {
+    fn drop(&mut self) {
+        if !self.nested {
+            self.draw();
+        }
+    }
+}
+
+impl<F: FnMut(Padding, usize) -> Container> Child for DeferScroll<F> {
+    fn push_into(&mut self, widgets: &mut Vec<TypelessWidget>) {
+        self.nested = true;
+        let mut flex = self.build();
+        if let Some(bg) = self.bg {
+            flex.commands.insert(
+                0,
+                Command {
+                    area: flex.area,
+                    primative: Primative::Ellipse(0, bg),
+                },
+            );
+        }
+        widgets.push(Container::from(flex).into());
+    }
+}
+
+impl<F: FnMut(Padding, usize) -> Container> DeferScroll<F> {
+    #[track_caller]
+    pub fn new(content: DeferContainer<F>) -> Self {
+        let location = Location::caller();
+        Self {
+            content,
+            id: (location.file(), location.line(), location.column(), 0),
+            x: 0,
+            y: 0,
+            width: None,
+            height: None,
+            smooth: true,
+            bg: None,
+            scrollbar: rgb(120, 120, 120),
+            nested: false,
+        }
+    }
+    pub fn draw(&mut self) {
+        let flex = self.build();
+
+        if let Some(bg) = self.bg {
+            queue_command(flex.area, Primative::Ellipse(0, bg))
+        };
+
+        for cmd in flex.commands {
+            cmd.queue();
+        }
+    }
+    /// Keeps the scroll position of containers created in the same place apart.
+    pub fn id(mut self, id: usize) -> Self {
+        self.id.3 = id;
+        self
+    }
+    pub fn gap(mut self, gap: usize) -> Self {
+        self.content.gap = gap;
+        self
+    }
+    pub fn padding(mut self, padding: usize) -> Self {
+        self.content.padding = Padding::new(padding, padding, padding, padding);
+        self
+    }
+    pub fn x(mut self, x: usize) -> Self {
+        self.x = x;
+        self
+    }
+    pub fn y(mut self, y: usize) -> Self {
+        self.y = y;
+        self
+    }
+    pub fn w(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+    pub fn h(mut self, height: usize) -> Self {
+        self.height = Some(height);
+        self
+    }
+    /// Jump straight to the new position instead of easing towards it.
+    pub fn smooth(mut self, smooth: bool) -> Self {
+        self.smooth = smooth;
+        self
+    }
+    pub fn bg(mut self, color: Color) -> Self {
+        self.bg = Some(color);
+        self
+    }
+    pub fn scrollbar(mut self, color: Color) -> Self {
+        self.scrollbar = color;
+        self
+    }
+
+    /// Mouse wheel and page keys scroll the view under the mouse, thumbs can be dragged
+    /// and clicking the rest of a scrollbar moves a page towards the mouse.
+    fn input(ctx: &mut Context, id: ScrollId, view: Rect, content: [usize; 2], max: [f32; 2]) -> &mut ScrollState {
+        let state = ctx.scroll.get_mut(id);
+        let mouse = ctx.window.mouse_position;
+        if mouse.intersects(view) {
+            let input = std::mem::take(&mut ctx.scroll_input);
+            let line = (default_font_size() * 3) as f32;
+            state.target[1] += input.lines * line + input.pages * view.height as f32;
+            if input.home {
+                state.target[1] = 0.0;
+            }
+            if input.end {
+                state.target[1] = max[1];
+            }
+        }
+
+        let down = ctx.window.left_mouse.pressed;
+        let just_pressed = down && !state.held;
+        state.held = down;
+
+        match state.drag {
+            Some((axis, start, target)) if down => {
+                let track = track(view, axis);
+                let thumb = thumb_rect(view, axis, content, state.offset[axis]);
+                let (position, track, thumb) = if axis == 0 {
+                    (mouse.x, track.width, thumb.width)
+                } else {
+                    (mouse.y, track.height, thumb.height)
+                };
+                let ratio = max[axis] / track.saturating_sub(thumb).max(1) as f32;
+                state.target[axis] = target + (position as f32 - start as f32) * ratio;
+            }
+            Some(_) => state.drag = None,
+            None if just_pressed => {
+                for axis in (0..2).filter(|axis| max[*axis] > 0.0) {
+                    if !mouse.intersects(track(view, axis)) {
+                        continue;
+                    }
+                    let thumb = thumb_rect(view, axis, content, state.offset[axis]);
+                    let (position, start, page) = if axis == 0 {
+                        (mouse.x, thumb.x, view.width)
+                    } else {
+                        (mouse.y, thumb.y, view.height)
+                    };
+                    if mouse.intersects(thumb) {
+                        state.drag = Some((axis, position, state.target[axis]));
+                    } else if position < start {
+                        state.target[axis] -= page as f32;
+                    } else {
+                        state.target[axis] += page as f32;
+                    }
+                    break;
+                }
+            }
+            None => {}
+        }
+        state
+    }
+}
+
+impl<F: FnMut(Padding, usize) -> Container> Defer for DeferScroll<F> {
+    type T = Flex;
+    fn build(&mut self) -> Self::T {
+        let mut container = self.content.build();
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 0, 0);
+
+        let content = [container.area.width, container.area.height];
+        let width = self.width.unwrap_or(content[0]);
+        let height = self
+            .height
+            .unwrap_or_else(|| content[1].min(viewport().height.saturating_sub(self.y)));
+        let view = Rect::new(self.x, self.y, width, height);
+        let max = [
+            content[0].saturating_sub(width) as f32,
+            content[1].saturating_sub(height) as f32,
+        ];
+
+        //Without a context there is no input and nothing to remember.
+        let mut default = ScrollState::default();
+        let state = match unsafe { CTX.as_mut() } {
+            Some(ctx) => Self::input(ctx, self.id, view, content, max),
+            None => &mut default,
+        };
+        state.update(max, self.smooth);
+        let state = *state;
+
+        let offset = (state.offset[0].round() as usize, state.offset[1].round() as usize);
+        let mut commands = vec![Command {
+            area: view,
+            primative: Primative::Layer(Rc::new(Layer { commands, offset })),
+        }];
+
+        //Scrollbars are drawn over the content.
+        for axis in (0..2).filter(|axis| max[*axis] > 0.0) {
+            commands.push(Command {
+                area: thumb_rect(view, axis, content, state.offset[axis]),
+                primative: Primative::Ellipse(SCROLLBAR_SIZE / 2, self.scrollbar),
+            });
+        }
+
+        Flex { commands, area: view }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn scrollbar() {
+        //Half of the content is visible.
+        assert_eq!(thumb(100, 100, 200, 0.0), (0, 50));
+        assert_eq!(thumb(100, 100, 200, 100.0), (50, 50));
+        assert_eq!(thumb(100, 100, 50, 0.0), (0, 100));
+
+        //Long content still has a thumb that can be grabbed.
+        assert_eq!(thumb(100, 100, 100_000, 0.0).1, MIN_THUMB);
+
+        let mut state = ScrollState {
+            target: [0.0, 500.0],
+            ..Default::default()
+        };
+        state.update([0.0, 100.0], false);
+        assert_eq!(state.offset, [0.0, 100.0]);
+
+        state.target[1] = 0.0;
+        state.update([0.0, 100.0], true);
+        assert_eq!(state.offset[1], 75.0);
+    }
+
+    #[test]
+    fn clipped() {
+        let flex = scroll!(rect().wh(50), rect().wh(50), rect().wh(50)).h(100).build();
+        assert_eq!(flex.area, Rect::new(0, 0, 50, 100));
+
+        let Primative::Layer(layer) = &flex.commands[0].primative else {
+            panic!("The content should be in a layer")
+        };
+        assert_eq!(layer.commands.len(), 3);
+        assert_eq!(layer.commands[2].area.y, 100);
+
+        //Only the vertical scrollbar is needed.
+        assert_eq!(flex.commands.len(), 2);
+        assert_eq!(flex.commands[1].area.x, 50 - SCROLLBAR_SIZE);
+    }
+
+    #[test]
+    fn nested() {
+        let mut container = h!(rect().wh(20), scroll!(rect().wh(50), rect().wh(50)).h(60))
+            .gap(5)
+            .build();
+        assert_eq!(container.area, Rect::new(0, 0, 75, 60));
+
+        let mut commands = Vec::new();
+        draw_widgets(&mut commands, &mut container, 10, 10);
+        assert_eq!(commands[1].area, Rect::new(35, 10, 50, 60));
+        assert!(matches!(commands[1].primative, Primative::Layer(_)));
+        assert_eq!(commands[2].area.x, 35 + 50 - SCROLLBAR_SIZE);
+    }
+}
@@ -0,0 +1,419 @@
+//! Keyboard shortcuts, a chord like `Ctrl+Shift+S` or a sequence of them like `g g`.
+//!
+//! ```rs
+//! ctx.shortcut("Ctrl+S".parse()?, || println!("Saved"));
+//!
+//! //Only while the widget has focus, these win over the ones on the context.
+//! Basic::default().shortcut("g g".parse()?, |_| println!("Top"));
+//! ```
+//!
+//! Shortcuts on the context get the keys that no focused widget or container handled.
+use crate::*;
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// The keys of a sequence have to be pressed within this long of each other.
+pub const SEQUENCE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Only this many keys are remembered for sequences.
+pub const MAX_SEQUENCE: usize = 8;
+
+/// A key and the modifiers held with it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chord {
+    pub key: Key,
+    pub modifiers: Modifiers,
+}
+
+impl Chord {
+    pub const fn new(key: Key, modifiers: Modifiers) -> Self {
+        Self { key, modifiers }
+    }
+
+    /// `Ctrl+Shift+S`, modifiers can be in any order and case is ignored.
+    pub fn parse(chord: &str) -> Option<Self> {
+        let mut modifiers = Modifiers::default();
+        let mut parts: Vec<&str> = chord.split('+').map(str::trim).collect();
+        let key = parts.pop()?;
+        for part in parts {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.control = true,
+                "shift" => modifiers.shift = true,
+                "alt" => modifiers.alt = true,
+                "win" | "super" | "cmd" | "meta" => modifiers.win = true,
+                _ => return None,
+            }
+        }
+        Some(Self::new(parse_key(key)?, modifiers))
+    }
+}
+
+fn parse_key(key: &str) -> Option<Key> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Char(c.to_ascii_lowercase()));
+    }
+
+    let lower = key.to_ascii_lowercase();
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+        return Some(Key::Function(n));
+    }
+
+    Some(match lower.as_str() {
+        "enter" | "return" => Key::Enter,
+        "space" => Key::Space,
+        "backspace" => Key::Backspace,
+        "escape" | "esc" => Key::Escape,
+        "tab" => Key::Tab,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "insert" => Key::Insert,
+        "delete" | "del" => Key::Delete,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        _ => return None,
+    })
+}
+
+impl std::fmt::Display for Chord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let m = self.modifiers;
+        for (held, name) in [
+            (m.control, "Ctrl+"),
+            (m.shift, "Shift+"),
+            (m.alt, "Alt+"),
+            (m.win, "Win+"),
+        ] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+        match self.key {
+            Key::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            Key::Function(n) => write!(f, "F{}", n),
+            key => write!(f, "{:?}", key),
+        }
+    }
+}
+
+/// One or more chords pressed one after the other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shortcut(pub Vec<Chord>);
+
+impl Shortcut {
+    /// Chords are separated by spaces, `Ctrl+K Ctrl+C`.
+    pub fn parse(shortcut: &str) -> Option<Self> {
+        let chords = shortcut
+            .split_whitespace()
+            .map(Chord::parse)
+            .collect::<Option<Vec<_>>>()?;
+        if chords.is_empty() {
+            return None;
+        }
+        Some(Self(chords))
+    }
+
+    /// The same, or one starts with the other so the shorter one would always run first.
+    pub fn conflicts(&self, other: &Shortcut) -> bool {
+        self.0.starts_with(&other.0) || other.0.starts_with(&self.0)
+    }
+}
+
+/// The shortcut that couldn't be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseShortcutError(pub String);
+
+impl std::fmt::Display for ParseShortcutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid shortcut: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseShortcutError {}
+
+impl FromStr for Shortcut {
+    type Err = ParseShortcutError;
+
+    fn from_str(shortcut: &str) -> Result<Self, Self::Err> {
+        Self::parse(shortcut).ok_or_else(|| ParseShortcutError(shortcut.to_string()))
+    }
+}
+
+impl TryFrom<&str> for Shortcut {
+    type Error = ParseShortcutError;
+
+    fn try_from(shortcut: &str) -> Result<Self, Self::Error> {
+        shortcut.parse()
+    }
+}
+
+impl std::fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, chord) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", chord)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Binding {
+    pub shortcut: Shortcut,
+    pub f: Box<dyn FnMut()>,
+}
+
+impl std::fmt::Debug for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Binding").field("shortcut", &self.shortcut).finish()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Shortcuts {
+    /// Shortcuts on the context, kept until they're removed.
+    pub bindings: Vec<Binding>,
+    /// Shortcuts on the widgets drawn last frame, they win over `bindings` while the widget has focus.
+    pub widgets: Vec<Shortcut>,
+    /// Shortcuts on the widgets drawn so far this frame.
+    pub drawn: Vec<Shortcut>,
+    /// The last chords pressed, `MAX_SEQUENCE` at most.
+    pub history: Vec<Chord>,
+    /// Every chord that's been recorded, including the ones no longer in `history`.
+    pub recorded: usize,
+    /// Chords before this one can't start a sequence, moved when a shortcut runs or after `SEQUENCE_TIMEOUT`.
+    pub start: usize,
+    pub last: Option<Instant>,
+}
+
+impl Shortcuts {
+    /// Returns the shortcuts it conflicts with, including the ones on widgets, it's registered either way.
+    pub fn register(&mut self, shortcut: Shortcut, f: impl FnMut() + 'static) -> Vec<Shortcut> {
+        let conflicts = self
+            .bindings
+            .iter()
+            .map(|b| &b.shortcut)
+            .chain(&self.widgets)
+            .filter(|s| s.conflicts(&shortcut))
+            .cloned()
+            .collect();
+        self.bindings.push(Binding {
+            shortcut,
+            f: Box::new(f),
+        });
+        conflicts
+    }
+
+    pub fn unregister(&mut self, shortcut: &Shortcut) {
+        self.bindings.retain(|b| b.shortcut != *shortcut);
+    }
+
+    /// Every pair of registered shortcuts that conflict.
+    /// Widget shortcuts are only checked against the context, one widget has focus at a time.
+    pub fn conflicts(&self) -> Vec<(Shortcut, Shortcut)> {
+        let mut conflicts = Vec::new();
+        for (i, a) in self.bindings.iter().enumerate() {
+            for b in self.bindings[i + 1..].iter().map(|b| &b.shortcut).chain(&self.widgets) {
+                if a.shortcut.conflicts(b) {
+                    conflicts.push((a.shortcut.clone(), b.clone()));
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// A widget with `shortcut` was drawn this frame.
+    pub fn drawn(&mut self, shortcut: &Shortcut) {
+        if !self.drawn.contains(shortcut) {
+            self.drawn.push(shortcut.clone());
+        }
+    }
+
+    /// Called once all of the widgets have been drawn.
+    pub fn next_frame(&mut self) {
+        self.widgets = std::mem::take(&mut self.drawn);
+    }
+
+    /// Returns how many chords have been recorded, kept with the key so it's matched against the chords up to it.
+    /// Modifier keys on their own aren't part of a shortcut.
+    pub fn record(&mut self, key: &Key, modifiers: &Modifiers) -> usize {
+        if matches!(
+            key,
+            Key::Control | Key::Shift | Key::Alt | Key::LeftWindows | Key::RightWindows
+        ) {
+            return self.recorded;
+        }
+
+        let now = Instant::now();
+        if self.last.is_some_and(|last| now - last > SEQUENCE_TIMEOUT) {
+            self.start = self.recorded;
+        }
+        self.last = Some(now);
+
+        self.history.push(Chord::new(*key, *modifiers));
+        if self.history.len() > MAX_SEQUENCE {
+            self.history.remove(0);
+        }
+        self.recorded += 1;
+        self.recorded
+    }
+
+    /// The chords pressed since the last shortcut ran, up to the one numbered `chords` by `record`.
+    pub fn sequence(&self, chords: usize) -> &[Chord] {
+        let first = self.recorded - self.history.len();
+        let start = self.start.max(first);
+        if chords < start {
+            return &[];
+        }
+        &self.history[start - first..chords - first]
+    }
+
+    /// The shortcut was finished by `key`, which was numbered `chords` by `record`.
+    pub fn matches(&self, shortcut: &Shortcut, key: &Key, modifiers: &Modifiers, chords: usize) -> bool {
+        shortcut.0.last() == Some(&Chord::new(*key, *modifiers)) && self.sequence(chords).ends_with(&shortcut.0)
+    }
+
+    /// Mark the keys that finish `shortcut` as handled, returns how many did.
+    pub fn handle(&mut self, shortcut: &Shortcut, keys: &mut [(Key, Modifiers, usize, bool)]) -> usize {
+        let mut count = 0;
+        for (key, modifiers, chords, handled) in keys.iter_mut().filter(|(_, _, _, handled)| !handled) {
+            if self.matches(shortcut, key, modifiers, *chords) {
+                self.start = *chords;
+                *handled = true;
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Run the shortcuts on the context for any keys that weren't handled this frame.
+    pub fn run(&mut self, keys: &mut [(Key, Modifiers, usize, bool)]) {
+        for (key, modifiers, chords, handled) in keys.iter_mut().filter(|(_, _, _, handled)| !handled) {
+            let Some(i) = self
+                .bindings
+                .iter()
+                .position(|b| self.matches(&b.shortcut, key, modifiers, *chords))
+            else {
+                continue;
+            };
+            (self.bindings[i].f)();
+            self.start = *chords;
+            *handled = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::{cell::Cell, rc::Rc};
+
+    #[test]
+    fn shortcuts() {
+        let ctrl_shift = Modifiers {
+            control: true,
+            shift: true,
+            ..Default::default()
+        };
+        let none = Modifiers::default();
+        let shortcut = |s: &str| s.parse::<Shortcut>().unwrap();
+
+        assert_eq!(
+            Chord::parse("ctrl+SHIFT+s"),
+            Some(Chord::new(Key::Char('s'), ctrl_shift))
+        );
+        assert_eq!(Chord::parse("F5"), Some(Chord::new(Key::Function(5), none)));
+        assert_eq!(Chord::parse("Hyper+S"), None);
+        assert_eq!(shortcut("g g").0.len(), 2);
+        assert_eq!(shortcut("Shift+Ctrl+s").to_string(), "Ctrl+Shift+S");
+        assert_eq!(Shortcut::parse(""), None);
+        assert_eq!(
+            "Ctrl++".parse::<Shortcut>(),
+            Err(ParseShortcutError("Ctrl++".to_string()))
+        );
+        assert!(Shortcut::try_from("Hyper+S").is_err());
+
+        let mut shortcuts = Shortcuts::default();
+        let saved = Rc::new(Cell::new(0));
+        let top = Rc::new(Cell::new(0));
+        let (s, t) = (saved.clone(), top.clone());
+        assert!(shortcuts
+            .register(shortcut("Ctrl+Shift+S"), move || s.set(s.get() + 1))
+            .is_empty());
+        assert!(shortcuts
+            .register(shortcut("g g"), move || t.set(t.get() + 1))
+            .is_empty());
+        assert_eq!(shortcuts.register(shortcut("g"), || {}), vec![shortcut("g g")]);
+        assert_eq!(shortcuts.conflicts().len(), 1);
+        shortcuts.unregister(&shortcut("g"));
+
+        //Widget shortcuts are checked against the context once they've been drawn.
+        shortcuts.drawn(&shortcut("g"));
+        shortcuts.drawn(&shortcut("g"));
+        assert!(shortcuts.conflicts().is_empty());
+        shortcuts.next_frame();
+        assert_eq!(shortcuts.conflicts(), vec![(shortcut("g g"), shortcut("g"))]);
+        assert_eq!(
+            shortcuts.register(shortcut("g g g"), || {}),
+            vec![shortcut("g g"), shortcut("g")]
+        );
+        shortcuts.unregister(&shortcut("g g g"));
+        shortcuts.next_frame();
+        assert!(shortcuts.conflicts().is_empty());
+
+        //Every key is matched against the chords pressed up to it, even when they arrive in the same frame.
+        let frame = |shortcuts: &mut Shortcuts, keys: &[(Key, Modifiers)]| {
+            let mut keys: Vec<_> = keys
+                .iter()
+                .map(|(key, modifiers)| (*key, *modifiers, shortcuts.record(key, modifiers), false))
+                .collect();
+            shortcuts.run(&mut keys);
+            keys.iter().map(|(_, _, _, handled)| *handled).collect::<Vec<_>>()
+        };
+
+        //Holding the modifiers doesn't break up a sequence.
+        assert_eq!(frame(&mut shortcuts, &[(Key::Control, none)]), [false]);
+        assert_eq!(frame(&mut shortcuts, &[(Key::Char('s'), ctrl_shift)]), [true]);
+        assert_eq!(saved.get(), 1);
+        assert_eq!(frame(&mut shortcuts, &[(Key::Char('s'), none)]), [false]);
+
+        assert_eq!(frame(&mut shortcuts, &[(Key::Char('g'), none)]), [false]);
+        assert_eq!(frame(&mut shortcuts, &[(Key::Char('g'), none)]), [true]);
+        assert_eq!(top.get(), 1);
+
+        //The sequence starts again once a shortcut runs.
+        assert_eq!(frame(&mut shortcuts, &[(Key::Char('g'), none)]), [false]);
+
+        let keys = [
+            (Key::Char('s'), ctrl_shift),
+            (Key::Char('g'), none),
+            (Key::Char('x'), none),
+        ];
+        assert_eq!(frame(&mut shortcuts, &keys), [true, false, false]);
+        assert_eq!(saved.get(), 2);
+        assert_eq!(top.get(), 1);
+
+        let keys = [(Key::Char('g'), none), (Key::Char('g'), none), (Key::Char('g'), none)];
+        assert_eq!(frame(&mut shortcuts, &keys), [false, true, false]);
+        assert_eq!(top.get(), 2);
+
+        //Keys that were already handled by a widget are skipped.
+        let chords = shortcuts.record(&Key::Char('g'), &none);
+        let mut keys = [(Key::Char('g'), none, chords, true)];
+        shortcuts.run(&mut keys);
+        assert_eq!(top.get(), 2);
+
+        //Widgets handle their own shortcuts the same way.
+        let chords = shortcuts.record(&Key::Char('g'), &none);
+        let mut keys = [(Key::Char('g'), none, chords, false)];
+        assert_eq!(shortcuts.handle(&shortcut("g g"), &mut keys), 1);
+        assert!(keys[0].3);
+    }
+}
@@ -3,10 +3,12 @@
 //!
 use crate::*;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Basic {
     pub area: Rect,
     pub behaviour: Vec<Click<Self>>,
+    pub id: usize,
+    pub item: FlexItem,
 }
 
 impl Widget for Basic {
@@ -16,6 +18,14 @@ impl Widget for Basic {
         Some(&mut self.behaviour)
     }
 
+    fn id_mut(&mut self) -> Option<&mut usize> {
+        Some(&mut self.id)
+    }
+
+    fn handler(&self) -> Option<Handler> {
+        click_handler(self)
+    }
+
     fn primative(&self) -> Primative {
         Primative::Ellipse(0, white())
     }
@@ -27,4 +37,12 @@ impl Widget for Basic {
     fn area_mut(&mut self) -> Option<&mut Rect> {
         Some(&mut self.area)
     }
+
+    fn item(&self) -> FlexItem {
+        self.item
+    }
+
+    fn item_mut(&mut self) -> Option<&mut FlexItem> {
+        Some(&mut self.item)
+    }
 }
@@ -0,0 +1,507 @@
+//! Bidirectional text and Arabic joining.
+//!
+//! This is a cut down version of the [Unicode Bidirectional Algorithm](https://unicode.org/reports/tr9/).
+//! There are no explicit embeddings or isolates and every line is treated as it's own paragraph.
+//! Arabic is shaped into the presentation forms, so the font needs to include them.
+use crate::*;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextDirection {
+    #[default]
+    LeftToRight,
+    RightToLeft,
+}
+
+/// https://www.unicode.org/reports/tr9/#Bidirectional_Character_Types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BidiClass {
+    L,
+    R,
+    AL,
+    EN,
+    ES,
+    ET,
+    AN,
+    CS,
+    NSM,
+    B,
+    S,
+    WS,
+    ON,
+}
+
+/// Only the common ranges are covered, everything else is left to right.
+pub fn bidi_class(c: char) -> BidiClass {
+    use BidiClass::*;
+    match c as u32 {
+        0x30..=0x39 => EN,
+        0x2B | 0x2D => ES,
+        0x23..=0x25 | 0xA2..=0xA5 | 0xB0 | 0xB1 | 0x20A0..=0x20CF => ET,
+        0x2C | 0x2E | 0x2F | 0x3A | 0xA0 => CS,
+        0x0A | 0x0D | 0x1C..=0x1E | 0x85 | 0x2029 => B,
+        0x09 | 0x0B | 0x1F => S,
+        0x0C | 0x20 | 0x2000..=0x200A | 0x2028 | 0x205F | 0x3000 => WS,
+        0x21 | 0x22 | 0x26..=0x2A | 0x3B..=0x40 | 0x5B..=0x60 | 0x7B..=0x7E => ON,
+        0xA1 | 0xA6..=0xA9 | 0xAB..=0xAF | 0xB4 | 0xB6..=0xB8 | 0xBB..=0xBF | 0xD7 | 0xF7 => ON,
+        0x0300..=0x036F => NSM,
+        0x0591..=0x05BD | 0x05BF | 0x05C1 | 0x05C2 | 0x05C4 | 0x05C5 | 0x05C7 => NSM,
+        0x0590..=0x05FF | 0x07C0..=0x085F | 0xFB1D..=0xFB4F => R,
+        0x0610..=0x061A | 0x064B..=0x065F | 0x0670 | 0x06D6..=0x06DC | 0x06DF..=0x06E4 => NSM,
+        0x06E7 | 0x06E8 | 0x06EA..=0x06ED => NSM,
+        0x0660..=0x0669 | 0x066B | 0x066C => AN,
+        0x06F0..=0x06F9 => EN,
+        0x0600..=0x07BF | 0x0860..=0x08FF | 0xFB50..=0xFDFF | 0xFE70..=0xFEFE => AL,
+        0x2010..=0x2027 | 0x2030..=0x205E | 0x2190..=0x2BFF | 0x1F000..=0x1FAFF => ON,
+        _ => L,
+    }
+}
+
+/// The direction of the first strong character.
+pub fn paragraph_direction(text: &str) -> TextDirection {
+    for c in text.chars() {
+        match bidi_class(c) {
+            BidiClass::L => return TextDirection::LeftToRight,
+            BidiClass::R | BidiClass::AL => return TextDirection::RightToLeft,
+            _ => {}
+        }
+    }
+    TextDirection::LeftToRight
+}
+
+/// Resolve the embedding level of every character in a line.
+/// Even levels are left to right, odd levels are right to left.
+pub fn resolve_levels(classes: &[BidiClass], direction: TextDirection) -> Vec<u8> {
+    use BidiClass::*;
+
+    let n = classes.len();
+    let base = (direction == TextDirection::RightToLeft) as u8;
+    let sos = if base == 1 { R } else { L };
+    let mut types = classes.to_vec();
+
+    //W1: Non-spacing marks take the type of the previous character.
+    let mut prev = sos;
+    for t in types.iter_mut() {
+        if *t == NSM {
+            *t = prev;
+        } else {
+            prev = *t;
+        }
+    }
+
+    //W2: European numbers after Arabic letters are Arabic numbers.
+    //W3: Arabic letters are right to left.
+    let mut last_strong = sos;
+    for t in types.iter_mut() {
+        match *t {
+            L | R => last_strong = *t,
+            AL => {
+                last_strong = AL;
+                *t = R;
+            }
+            EN if last_strong == AL => *t = AN,
+            _ => {}
+        }
+    }
+
+    //W4: A single separator between two numbers of the same type.
+    for i in 1..n.saturating_sub(1) {
+        let (before, after) = (types[i - 1], types[i + 1]);
+        match types[i] {
+            ES if before == EN && after == EN => types[i] = EN,
+            CS if before == after && (before == EN || before == AN) => types[i] = before,
+            _ => {}
+        }
+    }
+
+    //W5: Terminators next to european numbers.
+    let mut i = 0;
+    while i < n {
+        if types[i] != ET {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < n && types[i] == ET {
+            i += 1;
+        }
+        if (start > 0 && types[start - 1] == EN) || (i < n && types[i] == EN) {
+            types[start..i].fill(EN);
+        }
+    }
+
+    //W6: Any other separators and terminators are neutral.
+    for t in types.iter_mut() {
+        if matches!(*t, ES | ET | CS) {
+            *t = ON;
+        }
+    }
+
+    //W7: European numbers in left to right text.
+    let mut last_strong = sos;
+    for t in types.iter_mut() {
+        match *t {
+            L | R => last_strong = *t,
+            EN if last_strong == L => *t = L,
+            _ => {}
+        }
+    }
+
+    //N1: Neutrals between two characters of the same direction take that direction.
+    //N2: Otherwise they take the paragraph direction.
+    let strong = |t: BidiClass| match t {
+        L => Some(L),
+        R | EN | AN => Some(R),
+        _ => None,
+    };
+    let mut i = 0;
+    while i < n {
+        if strong(types[i]).is_some() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < n && strong(types[i]).is_none() {
+            i += 1;
+        }
+        let before = if start == 0 {
+            sos
+        } else {
+            strong(types[start - 1]).unwrap()
+        };
+        let after = if i == n { sos } else { strong(types[i]).unwrap() };
+        types[start..i].fill(if before == after { before } else { sos });
+    }
+
+    //I1 and I2
+    let mut levels: Vec<u8> = types
+        .iter()
+        .map(|t| match (base, t) {
+            (0, R) => 1,
+            (0, AN | EN) => 2,
+            (0, _) => 0,
+            (_, L | EN | AN) => 2,
+            (_, _) => 1,
+        })
+        .collect();
+
+    //L1: Separators and any whitespace before them or at the end of the line use the paragraph level.
+    let mut reset = true;
+    for i in (0..n).rev() {
+        match classes[i] {
+            S | B => {
+                levels[i] = base;
+                reset = true;
+            }
+            WS if reset => levels[i] = base,
+            _ => reset = false,
+        }
+    }
+
+    levels
+}
+
+/// The logical index of each character in visual order (L2).
+pub fn visual_order(levels: &[u8]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+    let max = levels.iter().copied().max().unwrap_or(0);
+    let Some(min_odd) = levels.iter().copied().filter(|l| l % 2 == 1).min() else {
+        return order;
+    };
+
+    for level in (min_odd..=max).rev() {
+        let mut i = 0;
+        while i < order.len() {
+            if levels[order[i]] < level {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < order.len() && levels[order[i]] >= level {
+                i += 1;
+            }
+            order[start..i].reverse();
+        }
+    }
+
+    order
+}
+
+/// Brackets are flipped in right to left text (L4).
+pub fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        '‹' => '›',
+        '›' => '‹',
+        _ => c,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Joining {
+    Dual,
+    Right,
+    Causing,
+    Transparent,
+    None,
+}
+
+//(isolated, final, initial, medial) for U+0622 to U+064A.
+#[rustfmt::skip]
+const ARABIC_FORMS: [[u16; 4]; 41] = [
+    [0xFE81, 0xFE82, 0, 0], [0xFE83, 0xFE84, 0, 0], [0xFE85, 0xFE86, 0, 0], [0xFE87, 0xFE88, 0, 0],
+    [0xFE89, 0xFE8A, 0xFE8B, 0xFE8C], [0xFE8D, 0xFE8E, 0, 0], [0xFE8F, 0xFE90, 0xFE91, 0xFE92],
+    [0xFE93, 0xFE94, 0, 0], [0xFE95, 0xFE96, 0xFE97, 0xFE98], [0xFE99, 0xFE9A, 0xFE9B, 0xFE9C],
+    [0xFE9D, 0xFE9E, 0xFE9F, 0xFEA0], [0xFEA1, 0xFEA2, 0xFEA3, 0xFEA4], [0xFEA5, 0xFEA6, 0xFEA7, 0xFEA8],
+    [0xFEA9, 0xFEAA, 0, 0], [0xFEAB, 0xFEAC, 0, 0], [0xFEAD, 0xFEAE, 0, 0], [0xFEAF, 0xFEB0, 0, 0],
+    [0xFEB1, 0xFEB2, 0xFEB3, 0xFEB4], [0xFEB5, 0xFEB6, 0xFEB7, 0xFEB8], [0xFEB9, 0xFEBA, 0xFEBB, 0xFEBC],
+    [0xFEBD, 0xFEBE, 0xFEBF, 0xFEC0], [0xFEC1, 0xFEC2, 0xFEC3, 0xFEC4], [0xFEC5, 0xFEC6, 0xFEC7, 0xFEC8],
+    [0xFEC9, 0xFECA, 0xFECB, 0xFECC], [0xFECD, 0xFECE, 0xFECF, 0xFED0],
+    [0; 4], [0; 4], [0; 4], [0; 4], [0; 4], [0; 4],
+    [0xFED1, 0xFED2, 0xFED3, 0xFED4], [0xFED5, 0xFED6, 0xFED7, 0xFED8], [0xFED9, 0xFEDA, 0xFEDB, 0xFEDC],
+    [0xFEDD, 0xFEDE, 0xFEDF, 0xFEE0], [0xFEE1, 0xFEE2, 0xFEE3, 0xFEE4], [0xFEE5, 0xFEE6, 0xFEE7, 0xFEE8],
+    [0xFEE9, 0xFEEA, 0xFEEB, 0xFEEC], [0xFEED, 0xFEEE, 0, 0], [0xFEEF, 0xFEF0, 0, 0],
+    [0xFEF1, 0xFEF2, 0xFEF3, 0xFEF4],
+];
+
+fn forms(c: char) -> Option<[u16; 4]> {
+    let forms = match c as u32 {
+        0x0622..=0x064A => ARABIC_FORMS[c as usize - 0x0622],
+        //Persian and Urdu
+        0x067E => [0xFB56, 0xFB57, 0xFB58, 0xFB59],
+        0x0686 => [0xFB7A, 0xFB7B, 0xFB7C, 0xFB7D],
+        0x0698 => [0xFB8A, 0xFB8B, 0, 0],
+        0x06A9 => [0xFB8E, 0xFB8F, 0xFB90, 0xFB91],
+        0x06AF => [0xFB92, 0xFB93, 0xFB94, 0xFB95],
+        0x06CC => [0xFBFC, 0xFBFD, 0xFBFE, 0xFBFF],
+        _ => return None,
+    };
+    (forms[0] != 0).then_some(forms)
+}
+
+fn joining(c: char) -> Joining {
+    match c as u32 {
+        //Tatweel and zero width joiner
+        0x0640 | 0x200D => Joining::Causing,
+        0x064B..=0x065F | 0x0670 => Joining::Transparent,
+        _ => match forms(c) {
+            Some([_, _, 0, 0]) => Joining::Right,
+            Some(_) => Joining::Dual,
+            None => Joining::None,
+        },
+    }
+}
+
+/// Lam followed by alef must be drawn as a single ligature.
+fn lam_alef(alef: char) -> Option<u32> {
+    match alef {
+        '\u{0622}' => Some(0xFEF5),
+        '\u{0623}' => Some(0xFEF7),
+        '\u{0625}' => Some(0xFEF9),
+        '\u{0627}' => Some(0xFEFB),
+        _ => None,
+    }
+}
+
+/// Replace Arabic letters with the form that joins to their neighbours.
+/// Returns the byte offset of every character, the alef in a lam-alef ligature is removed.
+pub fn shape_chars(text: &str) -> Vec<(usize, char)> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    if !chars.iter().any(|(_, c)| matches!(*c as u32, 0x0600..=0x06FF)) {
+        return chars;
+    }
+
+    let mut shaped = Vec::with_capacity(chars.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (index, c) = chars[i];
+        let Some(forms) = forms(c) else {
+            shaped.push((index, c));
+            i += 1;
+            continue;
+        };
+
+        let prev = chars[..i]
+            .iter()
+            .rev()
+            .map(|(_, c)| joining(*c))
+            .find(|j| *j != Joining::Transparent);
+        let joins_prev = matches!(prev, Some(Joining::Dual | Joining::Causing));
+        let next_index = (i + 1..chars.len()).find(|j| joining(chars[*j].1) != Joining::Transparent);
+
+        if c == '\u{0644}' {
+            if let Some(ligature) = next_index.and_then(|j| lam_alef(chars[j].1)) {
+                let ligature = if joins_prev { ligature + 1 } else { ligature };
+                shaped.push((index, char::from_u32(ligature).unwrap()));
+                //Keep any marks between the lam and alef.
+                let j = next_index.unwrap();
+                shaped.extend_from_slice(&chars[i + 1..j]);
+                i = j + 1;
+                continue;
+            }
+        }
+
+        let next = next_index.map(|j| joining(chars[j].1));
+        let joins_next = forms[2] != 0 && matches!(next, Some(Joining::Dual | Joining::Right | Joining::Causing));
+        let form = match (joins_prev, joins_next) {
+            (true, true) => forms[3],
+            (true, false) => forms[1],
+            (false, true) => forms[2],
+            (false, false) => forms[0],
+        };
+
+        shaped.push((index, char::from_u32(form as u32).unwrap_or(c)));
+        i += 1;
+    }
+
+    shaped
+}
+
+/// The characters of a line in the order they are drawn from left to right.
+/// Each character has it's byte offset into the line and if it's right to left.
+pub fn visual_chars(line: &str, direction: TextDirection) -> Vec<(usize, char, bool)> {
+    let shaped = shape_chars(line);
+    let classes: Vec<BidiClass> = shaped.iter().map(|(_, c)| bidi_class(*c)).collect();
+
+    if direction == TextDirection::LeftToRight
+        && !classes
+            .iter()
+            .any(|c| matches!(c, BidiClass::R | BidiClass::AL | BidiClass::AN))
+    {
+        return shaped.into_iter().map(|(i, c)| (i, c, false)).collect();
+    }
+
+    let levels = resolve_levels(&classes, direction);
+    visual_order(&levels)
+        .into_iter()
+        .map(|i| {
+            let (index, c) = shaped[i];
+            let rtl = levels[i] % 2 == 1;
+            (index, if rtl { mirror(c) } else { c }, rtl)
+        })
+        .collect()
+}
+
+/// The line as it appears on screen.
+pub fn visual_line(line: &str) -> String {
+    visual_chars(line, paragraph_direction(line))
+        .into_iter()
+        .map(|(_, c, _)| c)
+        .collect()
+}
+
+impl TextAlign {
+    /// Left and right swap places in right to left paragraphs.
+    pub fn resolve(self, direction: TextDirection, last_line: bool) -> TextAlign {
+        match (direction, self) {
+            (TextDirection::LeftToRight, _) => self,
+            (TextDirection::RightToLeft, TextAlign::Left) => TextAlign::Right,
+            (TextDirection::RightToLeft, TextAlign::Right) => TextAlign::Left,
+            (TextDirection::RightToLeft, TextAlign::Justify) if last_line => TextAlign::Right,
+            (TextDirection::RightToLeft, _) => self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn direction() {
+        assert_eq!(paragraph_direction("שלום abc"), TextDirection::RightToLeft);
+        assert_eq!(paragraph_direction("abc שלום"), TextDirection::LeftToRight);
+        assert_eq!(paragraph_direction("123 ..."), TextDirection::LeftToRight);
+    }
+
+    #[test]
+    fn reorder() {
+        assert_eq!(visual_line("hello"), "hello");
+        assert_eq!(visual_line("abc שלום"), "abc םולש");
+        assert_eq!(visual_line("שלום abc"), "abc םולש");
+
+        //Numbers keep their order.
+        assert_eq!(visual_line("שלום 123"), "123 םולש");
+
+        //Brackets are mirrored.
+        assert_eq!(visual_line("(שלום)"), "(םולש)");
+    }
+
+    #[test]
+    fn arabic() {
+        //Beh beh, initial then final.
+        let shaped: Vec<char> = shape_chars("بب").into_iter().map(|(_, c)| c).collect();
+        assert_eq!(shaped, ['\u{FE91}', '\u{FE90}']);
+
+        //Lam alef ligature.
+        let shaped = shape_chars("لا");
+        assert_eq!(shaped, [(0, '\u{FEFB}')]);
+
+        //Drawn right to left.
+        assert_eq!(visual_line("بب"), "\u{FE90}\u{FE91}");
+    }
+
+    #[test]
+    fn measure() {
+        let text = "abc שלום";
+        let layout = measure_text(text, default_font().unwrap(), 20, None);
+
+        //The last letter is drawn first.
+        let hebrew = &layout.glyphs[4];
+        assert!(hebrew.rtl);
+        assert_eq!(hebrew.char, 'ם');
+        assert_eq!(hebrew.index, text.len() - 'ם'.len_utf8());
+
+        //Clicking on the right edge of the line puts the caret after the first hebrew letter.
+        assert_eq!(layout.hit_test(1000.0, 1.0), "abc ".len());
+    }
+
+    #[test]
+    fn wrapped_paragraph() {
+        let font = default_font().unwrap();
+        let run = |text| Run {
+            text,
+            font,
+            font_size: 20.0,
+        };
+        let width = line_width(font, "abc", 20.0).ceil() as usize;
+        let lines = layout_runs(&[run("שלום abc"), run(" def\nabc")], Some(width), 0);
+
+        //The paragraph carries on into the second run, the wrapped latin lines are still right to left.
+        let directions: Vec<TextDirection> = lines.iter().map(|l| l.direction).collect();
+        assert_eq!(
+            directions,
+            [
+                TextDirection::RightToLeft,
+                TextDirection::RightToLeft,
+                TextDirection::RightToLeft,
+                TextDirection::LeftToRight
+            ]
+        );
+    }
+
+    #[test]
+    fn alignment() {
+        assert_eq!(
+            TextAlign::Left.resolve(TextDirection::RightToLeft, false),
+            TextAlign::Right
+        );
+        assert_eq!(
+            TextAlign::Justify.resolve(TextDirection::RightToLeft, true),
+            TextAlign::Right
+        );
+        assert_eq!(
+            TextAlign::Left.resolve(TextDirection::LeftToRight, true),
+            TextAlign::Left
+        );
+    }
+}
@@ -0,0 +1,491 @@
+//! Color glyphs for emoji.
+//!
+//! fontdue only understands outlines, so the color tables are parsed here.
+//! COLR/CPAL layers are rasterized with fontdue and tinted with the palette.
+//! sbix and CBDT store PNGs, those need the `image` feature to decode.
+//!
+//! ```rs
+//! set_emoji_font(ColorFont::from_bytes(std::fs::read("NotoColorEmoji.ttf").unwrap()).unwrap());
+//! text("done ✅")
+//! ```
+use crate::*;
+use fontdue::{Font, FontSettings, Metrics};
+use std::{collections::HashMap, sync::Mutex};
+
+static mut EMOJI_FONT: Option<ColorFont> = None;
+
+/// Used for any character the font being drawn doesn't have.
+pub fn set_emoji_font(font: ColorFont) {
+    unsafe { EMOJI_FONT = Some(font) };
+}
+
+pub fn emoji_font() -> Option<&'static ColorFont> {
+    unsafe { EMOJI_FONT.as_ref() }
+}
+
+/// The glyph to draw instead when `font` doesn't have the character.
+pub fn fallback_glyph(font: &Font, char: char, font_size: f32, color: Color) -> Option<Arc<ColorGlyph>> {
+    if font.lookup_glyph_index(char) != 0 {
+        return None;
+    }
+    emoji_font()?.rasterize(char, font_size, color)
+}
+
+/// Advance width of the character, taking the emoji font into account.
+pub fn glyph_advance(font: &Font, char: char, font_size: f32) -> f32 {
+    if let Some(emoji) = emoji_font() {
+        if font.lookup_glyph_index(char) == 0 && emoji.font.lookup_glyph_index(char) != 0 {
+            return emoji.font.metrics(char, font_size).advance_width;
+        }
+    }
+    font.metrics(char, font_size).advance_width
+}
+
+#[derive(Debug, Clone)]
+pub struct ColorGlyph {
+    pub metrics: Metrics,
+    /// Straight alpha, `metrics.width * metrics.height * 4` bytes.
+    pub rgba: Vec<u8>,
+}
+
+/// Straight alpha RGBA pixels, `width * height * 4` bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bitmap {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+}
+
+/// The cache is cleared once it holds this many glyphs, every size and color is cached separately.
+pub const MAX_CACHED_GLYPHS: usize = 1024;
+
+/// The character, font size bits and color of a cached glyph.
+pub type GlyphKey = (char, u32, u32);
+
+pub struct ColorFont {
+    pub font: Font,
+    pub data: Vec<u8>,
+    //Decoding PNGs every frame is far too slow.
+    cache: Mutex<HashMap<GlyphKey, Option<Arc<ColorGlyph>>>>,
+}
+
+impl ColorFont {
+    pub fn from_bytes(data: impl Into<Vec<u8>>) -> Option<Self> {
+        let data = data.into();
+        let font = Font::from_bytes(data.as_slice(), FontSettings::default()).ok()?;
+        Some(Self {
+            font,
+            data,
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Falls back to the outline tinted with `color` when there is no color data.
+    /// The color is also used for COLR layers that ask for the foreground color.
+    pub fn rasterize(&self, char: char, font_size: f32, color: Color) -> Option<Arc<ColorGlyph>> {
+        let key = (char, font_size.to_bits(), color.as_u32());
+        if let Some(glyph) = self.cache.lock().unwrap().get(&key) {
+            return glyph.clone();
+        }
+
+        let id = self.font.lookup_glyph_index(char);
+        let glyph = if id == 0 {
+            None
+        } else {
+            self.colr(id, font_size, color)
+                .or_else(|| self.bitmap(id, font_size))
+                .or_else(|| Some(self.outline(id, font_size, color)))
+                .map(Arc::new)
+        };
+
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= MAX_CACHED_GLYPHS {
+            cache.clear();
+        }
+        cache.insert(key, glyph.clone());
+        glyph
+    }
+
+    fn outline(&self, id: u16, font_size: f32, color: Color) -> ColorGlyph {
+        let (metrics, coverage) = self.font.rasterize_indexed(id, font_size);
+        let mut rgba = Vec::with_capacity(coverage.len() * 4);
+        for alpha in coverage {
+            rgba.extend_from_slice(&[color.r(), color.g(), color.b(), alpha]);
+        }
+        ColorGlyph { metrics, rgba }
+    }
+
+    /// COLR version 0, every layer is a regular outline with a palette color.
+    fn colr(&self, id: u16, font_size: f32, color: Color) -> Option<ColorGlyph> {
+        let colr = find_table(&self.data, b"COLR")?;
+        let cpal = find_table(&self.data, b"CPAL");
+        let base_count = u16_at(colr, 2)? as usize;
+        let base_offset = u32_at(colr, 4)? as usize;
+        let layer_offset = u32_at(colr, 8)? as usize;
+
+        let mut base = None;
+        for i in 0..base_count {
+            let record = base_offset + i * 6;
+            if u16_at(colr, record)? == id {
+                base = Some((u16_at(colr, record + 2)? as usize, u16_at(colr, record + 4)? as usize));
+                break;
+            }
+        }
+
+        let (first, count) = base?;
+        let mut layers = Vec::with_capacity(count);
+        for layer in first..first + count {
+            let record = layer_offset + layer * 4;
+            let layer_id = u16_at(colr, record)?;
+            let palette_index = u16_at(colr, record + 2)?;
+            let layer_color = if palette_index == 0xFFFF {
+                [color.r(), color.g(), color.b(), 255]
+            } else {
+                palette_color(cpal?, palette_index)?
+            };
+            let (metrics, coverage) = self.font.rasterize_indexed(layer_id, font_size);
+            layers.push((metrics, coverage, layer_color));
+        }
+
+        //Every layer has it's own bounds, the glyph needs to fit all of them.
+        let left = layers.iter().map(|(m, _, _)| m.xmin).min()?;
+        let bottom = layers.iter().map(|(m, _, _)| m.ymin).min()?;
+        let right = layers.iter().map(|(m, _, _)| m.xmin + m.width as i32).max()?;
+        let top = layers.iter().map(|(m, _, _)| m.ymin + m.height as i32).max()?;
+        let width = (right - left) as usize;
+        let height = (top - bottom) as usize;
+        let mut rgba = vec![0u8; width * height * 4];
+
+        for (metrics, coverage, layer_color) in &layers {
+            let ox = (metrics.xmin - left) as usize;
+            let oy = (top - metrics.ymin - metrics.height as i32) as usize;
+
+            for y in 0..metrics.height {
+                for x in 0..metrics.width {
+                    let alpha = coverage[x + y * metrics.width] as u32 * layer_color[3] as u32 / 255;
+                    if alpha == 0 {
+                        continue;
+                    }
+                    let i = ((oy + y) * width + ox + x) * 4;
+                    over(
+                        &mut rgba[i..i + 4],
+                        [layer_color[0], layer_color[1], layer_color[2], alpha as u8],
+                    );
+                }
+            }
+        }
+
+        Some(ColorGlyph {
+            metrics: Metrics {
+                xmin: left,
+                ymin: bottom,
+                width,
+                height,
+                ..self.font.metrics_indexed(id, font_size)
+            },
+            rgba,
+        })
+    }
+
+    #[cfg(not(feature = "image"))]
+    fn bitmap(&self, _id: u16, _font_size: f32) -> Option<ColorGlyph> {
+        None
+    }
+
+    #[cfg(feature = "image")]
+    fn bitmap(&self, id: u16, font_size: f32) -> Option<ColorGlyph> {
+        self.sbix(id, font_size).or_else(|| self.cbdt(id, font_size))
+    }
+
+    /// Apple's bitmap table, one strike per pixel size.
+    #[cfg(feature = "image")]
+    fn sbix(&self, id: u16, font_size: f32) -> Option<ColorGlyph> {
+        let sbix = find_table(&self.data, b"sbix")?;
+        let maxp = find_table(&self.data, b"maxp")?;
+        if id >= u16_at(maxp, 4)? {
+            return None;
+        }
+
+        let mut best: Option<(u16, usize)> = None;
+        for i in 0..u32_at(sbix, 4)? as usize {
+            let strike = u32_at(sbix, 8 + i * 4)? as usize;
+            let ppem = u16_at(sbix, strike)?;
+            if best.map_or(true, |(current, _)| better_strike(current, ppem, font_size)) {
+                best = Some((ppem, strike));
+            }
+        }
+
+        let (ppem, strike) = best?;
+        let start = u32_at(sbix, strike + 4 + id as usize * 4)? as usize;
+        let end = u32_at(sbix, strike + 4 + (id as usize + 1) * 4)? as usize;
+        let data = sbix.get(strike + start..strike + end)?;
+        if data.get(4..8)? != b"png " {
+            return None;
+        }
+
+        let origin_x = u16_at(data, 0)? as i16 as f32;
+        let origin_y = u16_at(data, 2)? as i16 as f32;
+        let bitmap = decode_png(data.get(8..)?)?;
+        Some(self.scale_bitmap(id, font_size, ppem, (origin_x, origin_y), bitmap))
+    }
+
+    /// Google's bitmap table, the locations are stored in CBLC and the PNGs in CBDT.
+    #[cfg(feature = "image")]
+    fn cbdt(&self, id: u16, font_size: f32) -> Option<ColorGlyph> {
+        let cblc = find_table(&self.data, b"CBLC")?;
+        let cbdt = find_table(&self.data, b"CBDT")?;
+
+        let mut best: Option<(u16, usize)> = None;
+        for i in 0..u32_at(cblc, 4)? as usize {
+            let record = 8 + i * 48;
+            if id < u16_at(cblc, record + 40)? || id > u16_at(cblc, record + 42)? {
+                continue;
+            }
+            let ppem = *cblc.get(record + 44)? as u16;
+            if best.map_or(true, |(current, _)| better_strike(current, ppem, font_size)) {
+                best = Some((ppem, record));
+            }
+        }
+
+        let (ppem, record) = best?;
+        let array = u32_at(cblc, record)? as usize;
+
+        for i in 0..u32_at(cblc, record + 8)? as usize {
+            let entry = array + i * 8;
+            let first = u16_at(cblc, entry)?;
+            if id < first || id > u16_at(cblc, entry + 2)? {
+                continue;
+            }
+
+            let subtable = array + u32_at(cblc, entry + 4)? as usize;
+            let image_format = u16_at(cblc, subtable + 2)?;
+            let image_offset = u32_at(cblc, subtable + 4)? as usize;
+            let g = (id - first) as usize;
+
+            let (start, end) = match u16_at(cblc, subtable)? {
+                1 => (
+                    u32_at(cblc, subtable + 8 + g * 4)? as usize,
+                    u32_at(cblc, subtable + 8 + (g + 1) * 4)? as usize,
+                ),
+                3 => (
+                    u16_at(cblc, subtable + 8 + g * 2)? as usize,
+                    u16_at(cblc, subtable + 8 + (g + 1) * 2)? as usize,
+                ),
+                //Sparse, pairs of (glyph id, offset).
+                4 => {
+                    let count = u32_at(cblc, subtable + 8)? as usize;
+                    let mut range = None;
+                    for j in 0..count {
+                        let pair = subtable + 12 + j * 4;
+                        if u16_at(cblc, pair)? == id {
+                            range = Some((u16_at(cblc, pair + 2)? as usize, u16_at(cblc, pair + 6)? as usize));
+                            break;
+                        }
+                    }
+                    range?
+                }
+                _ => return None,
+            };
+
+            let glyph = cbdt.get(image_offset + start..image_offset + end)?;
+            if glyph.len() < 8 {
+                return None;
+            }
+
+            //(height, bearing x, bearing y, offset of the PNG length)
+            let (height, bearing_x, bearing_y, length) = match image_format {
+                //Small metrics
+                17 => (glyph[0], glyph[2] as i8, glyph[3] as i8, 5),
+                //Big metrics
+                18 => (glyph[0], glyph[2] as i8, glyph[3] as i8, 8),
+                _ => return None,
+            };
+
+            let png_length = u32_at(glyph, length)? as usize;
+            let png = glyph.get(length + 4..length + 4 + png_length)?;
+            let bitmap = decode_png(png)?;
+            let origin = (bearing_x as f32, bearing_y as f32 - height as f32);
+            return Some(self.scale_bitmap(id, font_size, ppem, origin, bitmap));
+        }
+
+        None
+    }
+
+    /// Bitmap fonts only have a couple of sizes, scale the closest one to the font size.
+    /// The origin is the bottom left of the bitmap relative to the baseline.
+    fn scale_bitmap(&self, id: u16, font_size: f32, ppem: u16, origin: (f32, f32), bitmap: Bitmap) -> ColorGlyph {
+        let scale = font_size / ppem as f32;
+        let scaled_width = ((bitmap.width as f32 * scale).round() as usize).max(1);
+        let scaled_height = ((bitmap.height as f32 * scale).round() as usize).max(1);
+
+        ColorGlyph {
+            metrics: Metrics {
+                xmin: (origin.0 * scale).round() as i32,
+                ymin: (origin.1 * scale).round() as i32,
+                width: scaled_width,
+                height: scaled_height,
+                ..self.font.metrics_indexed(id, font_size)
+            },
+            rgba: resize_rgba(&bitmap, scaled_width, scaled_height).rgba,
+        }
+    }
+}
+
+#[cfg(feature = "image")]
+fn decode_png(data: &[u8]) -> Option<Bitmap> {
+    use zune_image::codecs::png::{zune_core::options::DecoderOptions, PngDecoder};
+
+    let options = DecoderOptions::default()
+        .png_set_strip_to_8bit(true)
+        .png_set_add_alpha_channel(true);
+    let mut decoder = PngDecoder::new_with_options(data.to_vec(), options);
+    let pixels = decoder.decode().ok()?.u8()?;
+    let (width, height) = decoder.dimensions()?;
+    Some(Bitmap {
+        width,
+        height,
+        rgba: pixels,
+    })
+}
+
+/// Prefer the smallest strike that is larger than the font size, downscaling looks better.
+fn better_strike(current: u16, candidate: u16, font_size: f32) -> bool {
+    let (current, candidate) = (current as f32, candidate as f32);
+    if current < font_size {
+        candidate > current
+    } else {
+        candidate >= font_size && candidate < current
+    }
+}
+
+/// (r, g, b, a) of the first palette.
+fn palette_color(cpal: &[u8], index: u16) -> Option<[u8; 4]> {
+    if index >= u16_at(cpal, 2)? {
+        return None;
+    }
+    let records = u32_at(cpal, 8)? as usize;
+    let first = u16_at(cpal, 12)? as usize;
+    let record = records + (first + index as usize) * 4;
+    //Stored as BGRA.
+    let bgra = cpal.get(record..record + 4)?;
+    Some([bgra[2], bgra[1], bgra[0], bgra[3]])
+}
+
+/// Straight alpha source over destination.
+pub fn over(dst: &mut [u8], src: [u8; 4]) {
+    let sa = src[3] as f32 / 255.0;
+    let da = dst[3] as f32 / 255.0;
+    let out = sa + da * (1.0 - sa);
+    if out == 0.0 {
+        return;
+    }
+
+    for c in 0..3 {
+        let color = (src[c] as f32 * sa + dst[c] as f32 * da * (1.0 - sa)) / out;
+        dst[c] = color.round() as u8;
+    }
+    dst[3] = (out * 255.0).round() as u8;
+}
+
+/// Box filter, each destination pixel is the alpha weighted average of the pixels it covers.
+pub fn resize_rgba(bitmap: &Bitmap, new_width: usize, new_height: usize) -> Bitmap {
+    let (width, height, src) = (bitmap.width, bitmap.height, &bitmap.rgba);
+    let mut dst = vec![0u8; new_width * new_height * 4];
+
+    for dy in 0..new_height {
+        let y0 = dy * height / new_height;
+        let y1 = ((dy + 1) * height / new_height).max(y0 + 1).min(height);
+
+        for dx in 0..new_width {
+            let x0 = dx * width / new_width;
+            let x1 = ((dx + 1) * width / new_width).max(x0 + 1).min(width);
+            let mut sum = [0u32; 4];
+            let mut count = 0;
+
+            for sy in y0..y1 {
+                for sx in x0..x1 {
+                    let i = (sy * width + sx) * 4;
+                    let alpha = src[i + 3] as u32;
+                    sum[0] += src[i] as u32 * alpha;
+                    sum[1] += src[i + 1] as u32 * alpha;
+                    sum[2] += src[i + 2] as u32 * alpha;
+                    sum[3] += alpha;
+                    count += 1;
+                }
+            }
+
+            let i = (dy * new_width + dx) * 4;
+            //Fully transparent pixels stay zeroed.
+            dst[i] = sum[0].checked_div(sum[3]).unwrap_or(0) as u8;
+            dst[i + 1] = sum[1].checked_div(sum[3]).unwrap_or(0) as u8;
+            dst[i + 2] = sum[2].checked_div(sum[3]).unwrap_or(0) as u8;
+            dst[i + 3] = sum[3].checked_div(count).unwrap_or(0) as u8;
+        }
+    }
+
+    Bitmap {
+        width: new_width,
+        height: new_height,
+        rgba: dst,
+    }
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// Look up a table in the font directory.
+pub fn find_table<'a>(data: &'a [u8], tag: &[u8; 4]) -> Option<&'a [u8]> {
+    for i in 0..u16_at(data, 4)? as usize {
+        let record = 12 + i * 16;
+        if data.get(record..record + 4)? == tag {
+            let offset = u32_at(data, record + 8)? as usize;
+            let length = u32_at(data, record + 12)? as usize;
+            return data.get(offset..offset + length);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn tables() {
+        assert!(find_table(FONT, b"glyf").is_some());
+        assert!(find_table(FONT, b"COLR").is_none());
+    }
+
+    #[test]
+    fn outline_fallback() {
+        //No color tables, so the outline is tinted instead.
+        let font = ColorFont::from_bytes(FONT).unwrap();
+        let glyph = font.rasterize('a', 20.0, red()).unwrap();
+        assert_eq!(glyph.rgba.len(), glyph.metrics.width * glyph.metrics.height * 4);
+        assert!(glyph.rgba.chunks(4).all(|px| px[..3] == [255, 0, 0]));
+    }
+
+    #[test]
+    fn blending() {
+        let mut dst = [0, 0, 255, 255];
+        over(&mut dst, [255, 0, 0, 255]);
+        assert_eq!(dst, [255, 0, 0, 255]);
+
+        let mut dst = [0, 0, 0, 0];
+        over(&mut dst, [255, 0, 0, 128]);
+        assert_eq!(dst, [255, 0, 0, 128]);
+
+        //2x2 -> 1x1, the transparent pixels don't darken the result.
+        let src = Bitmap {
+            width: 2,
+            height: 2,
+            rgba: vec![255, 0, 0, 255, 0, 0, 0, 0, 0, 0, 0, 0, 255, 0, 0, 255],
+        };
+        assert_eq!(resize_rgba(&src, 1, 1).rgba, [255, 0, 0, 127]);
+    }
+}
@@ -33,6 +33,8 @@ pub fn image(path: impl AsRef<Path>) -> Image {
                         format: ImageFormat::JPEG,
                         area: Rect::new(0, 0, width, height),
                         bitmap,
+                        size: (width, height),
+                        item: FlexItem::new().aspect_ratio(width, height),
                     }
                 }
                 "png" => {
@@ -44,6 +46,8 @@ pub fn image(path: impl AsRef<Path>) -> Image {
                         format: ImageFormat::PNG,
                         area: Rect::new(0, 0, width, height),
                         bitmap: bitmap.u8().unwrap(),
+                        size: (width, height),
+                        item: FlexItem::new().aspect_ratio(width, height),
                     }
                 }
                 _ => panic!("{} is not a supported image extension.", ext),
@@ -97,25 +101,14 @@ pub struct Image {
     pub format: ImageFormat,
     pub area: Rect,
     pub bitmap: Vec<u8>,
+    /// The width and height of the bitmap, the image is scaled to fit it's area.
+    pub size: (usize, usize),
+    pub item: FlexItem,
 }
 
 impl Image {}
 
 impl Widget for Image {
-    fn width<U: Into<Unit>>(mut self, length: U) -> Self
-    where
-        Self: Sized,
-    {
-        unimplemented!("The width of an image is fixed");
-    }
-
-    fn height<U: Into<Unit>>(mut self, length: U) -> Self
-    where
-        Self: Sized,
-    {
-        unimplemented!("The height of an image is fixed");
-    }
-
     #[inline]
     fn area(&self) -> Rect {
         self.area
@@ -126,10 +119,17 @@ impl Widget for Image {
         Some(&mut self.area)
     }
 
+    fn item(&self) -> FlexItem {
+        self.item
+    }
+
+    fn item_mut(&mut self) -> Option<&mut FlexItem> {
+        Some(&mut self.item)
+    }
+
     fn primative(&self) -> Primative {
         //TODO: Just assume the image exists for now.
-        let bitmap = unsafe { extend_lifetime(&self.bitmap) };
-        Primative::ImageUnsafe(bitmap, self.format)
+        Primative::ImageUnsafe(unsafe { extend_lifetime(self) })
         // Some(Command::Image(
         //     self.bitmap.clone().into_boxed_slice(),
         //     self.area.x as usize,
@@ -1,5 +1,5 @@
 pub mod rectangle;
-use std::{ops::Deref, slice::Iter};
+use std::{any::Any, ops::Deref, panic::Location, slice::Iter, sync::Arc};
 
 pub use rectangle::*;
 
@@ -20,6 +20,15 @@ pub use image::*;
 pub mod text;
 pub use text::*;
 
+pub mod rich_text;
+pub use rich_text::*;
+
+pub mod color_font;
+pub use color_font::*;
+
+pub mod bidi;
+pub use bidi::*;
+
 #[cfg(target_os = "windows")]
 #[cfg(feature = "dwrite")]
 pub mod dwrite;
@@ -45,70 +54,331 @@ where
     //This one does not
     fn area_mut(&mut self) -> Option<&mut Rect>;
 
+    /// Distance from the top of the widget to the first baseline.
+    /// Used to line up text in a row.
+    fn baseline(&self) -> Option<usize> {
+        None
+    }
+
+    /// The size of one `em`, used to resolve `Unit::Em`.
+    fn em_size(&self) -> usize {
+        default_font_size()
+    }
+
+    /// How the widget is sized inside a `h!` or `v!`.
+    fn item(&self) -> FlexItem {
+        FlexItem::default()
+    }
+
+    fn item_mut(&mut self) -> Option<&mut FlexItem> {
+        None
+    }
+
     fn behaviour(&mut self) -> Option<&mut Vec<Click<Self>>> {
         None
     }
 
+    /// Set with `id`.
+    fn id_mut(&mut self) -> Option<&mut usize> {
+        None
+    }
+
+    /// Widgets without any behaviour don't have an id.
+    fn widget_id(&mut self) -> Option<WidgetId> {
+        let id = self.id_mut().map_or(0, |id| *id);
+        let location = self.behaviour()?.first()?.location;
+        Some((location.file(), location.line(), location.column(), id))
+    }
+
     unsafe fn is_container(&self) -> bool {
         false
     }
 
+    /// Keeps the behaviour of the widget when it's placed inside of a layout.
+    fn handler(&self) -> Option<Handler> {
+        None
+    }
+
+    /// Containers are built here so they can be kept together inside of another layout.
+    fn build_container(&mut self) -> Option<Container> {
+        None
+    }
+
     #[inline]
     unsafe fn as_slice(&mut self) -> &[Self::Layout] {
         unsafe { core::mem::transmute(core::slice::from_ref(self)) }
     }
 
     fn try_click(&mut self) {
-        let ctx = ctx(); //Could pass this in instead.
+        self.run_click(ctx());
+    }
+
+    fn run_click(&mut self, ctx: &mut Context) {
         let area = self.area();
+        let id = self.widget_id().unwrap_or_default();
         let behaviour = std::mem::take(self.behaviour().unwrap());
-
-        for b in &behaviour {
+        let mouse = ctx.window.mouse_position;
+
+        let hover = ctx.register_hover(id, area);
+        let draggable = behaviour.iter().any(|b| b.action.is_drag());
+        let (source, target) = ctx.register_drag(hover, area, draggable);
+        let focused = behaviour.iter().any(|b| b.action.is_focus()) && {
+            let requested = behaviour.iter().any(|b| b.action == Action::FocusRequested);
+            ctx.register_focus(hover, area, requested)
+        };
+        ctx.drag.accepted = true;
+
+        let accepts = behaviour.iter().filter(|b| b.action == Action::Accepts);
+        for b in accepts.chain(behaviour.iter().filter(|b| b.action != Action::Accepts)) {
             if match b.action {
-                MouseAction::Pressed => pressed(ctx, area, b.button),
-                MouseAction::Released => released(ctx, area, b.button),
-                MouseAction::Clicked => clicked(ctx, area, b.button),
+                Action::Mouse(MouseAction::Pressed) => pressed(ctx, area, b.button),
+                Action::Mouse(MouseAction::Released) => released(ctx, area, b.button),
+                Action::Mouse(MouseAction::Clicked) => clicked(ctx, area, b.button),
+                Action::Mouse(MouseAction::DoubleClicked) => double_clicked(ctx, area, b.button),
+                Action::Mouse(MouseAction::LongPressed) => long_pressed(ctx, area, b.button),
+                Action::Mouse(MouseAction::Hovered) => ctx.hover.hovered(mouse, hover),
+                Action::Mouse(MouseAction::Entered) => ctx.hover.entered(mouse, hover),
+                Action::Mouse(MouseAction::Exited) => ctx.hover.exited(mouse, hover),
+                Action::Mouse(MouseAction::DragStarted) => source && ctx.drag.phase == DragPhase::Started,
+                Action::Mouse(MouseAction::Dragged) => source && ctx.drag.is_active(),
+                Action::Mouse(MouseAction::DragEnded) => source && ctx.drag.phase == DragPhase::Ended,
+                Action::Mouse(MouseAction::Dropped) => target && ctx.drag.accepted && ctx.drag.payload.is_some(),
+                Action::Accepts => target,
+                Action::Focusable | Action::FocusRequested => false,
+                Action::KeyPressed => focused && ctx.focus.pending(),
+                Action::Drawn => true,
             } {
-                (b.function)(self);
+                b.call(self);
             }
         }
 
         *self.behaviour().unwrap() = behaviour;
     }
 
-    fn on_click(mut self, button: MouseButton, function: fn(&mut Self)) -> Self {
+    /// Keeps widgets with behaviour added in the same place apart, like the ones created in a loop.
+    fn id(mut self, id: usize) -> Self {
+        if let Some(i) = self.id_mut() {
+            *i = id;
+        }
+        self
+    }
+
+    /// State the closure changes has to be shared, `move |_| count.set(count.get() + 1)` with an `Rc<Cell<_>>`.
+    /// The closure can't borrow it, see `ClickFn`.
+    #[track_caller]
+    fn on_click(mut self, button: MouseButton, function: impl FnMut(&mut Self) + 'static) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            behaviour.push(Click::new(button, MouseAction::Clicked, function));
+        }
+        self
+    }
+
+    #[track_caller]
+    fn on_double_click(mut self, button: MouseButton, function: impl FnMut(&mut Self) + 'static) -> Self {
         if let Some(behaviour) = self.behaviour() {
-            behaviour.push(Click {
-                button,
-                action: MouseAction::Clicked,
-                function,
-            });
+            behaviour.push(Click::new(button, MouseAction::DoubleClicked, function));
         }
         self
     }
 
-    fn on_pressed(mut self, button: MouseButton, function: fn(&mut Self)) -> Self {
+    #[track_caller]
+    fn on_long_press(mut self, button: MouseButton, function: impl FnMut(&mut Self) + 'static) -> Self {
         if let Some(behaviour) = self.behaviour() {
-            behaviour.push(Click {
-                button,
-                action: MouseAction::Pressed,
-                function,
-            });
+            behaviour.push(Click::new(button, MouseAction::LongPressed, function));
         }
         self
     }
 
-    fn on_released(mut self, button: MouseButton, function: fn(&mut Self)) -> Self {
+    #[track_caller]
+    fn on_pressed(mut self, button: MouseButton, function: impl FnMut(&mut Self) + 'static) -> Self {
         if let Some(behaviour) = self.behaviour() {
-            behaviour.push(Click {
-                button,
-                action: MouseAction::Released,
-                function,
-            });
+            behaviour.push(Click::new(button, MouseAction::Pressed, function));
         }
         self
     }
 
+    #[track_caller]
+    fn on_released(mut self, button: MouseButton, function: impl FnMut(&mut Self) + 'static) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            behaviour.push(Click::new(button, MouseAction::Released, function));
+        }
+        self
+    }
+
+    /// Called every frame the mouse is over the widget.
+    #[track_caller]
+    fn on_hover(mut self, function: impl FnMut(&mut Self) + 'static) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            behaviour.push(Click::new(MouseButton::Left, MouseAction::Hovered, function));
+        }
+        self
+    }
+
+    #[track_caller]
+    fn on_enter(mut self, function: impl FnMut(&mut Self) + 'static) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            behaviour.push(Click::new(MouseButton::Left, MouseAction::Entered, function));
+        }
+        self
+    }
+
+    #[track_caller]
+    fn on_leave(mut self, function: impl FnMut(&mut Self) + 'static) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            behaviour.push(Click::new(MouseButton::Left, MouseAction::Exited, function));
+        }
+        self
+    }
+
+    /// The mouse has moved past `DRAG_THRESHOLD` while pressed on the widget.
+    #[track_caller]
+    fn on_drag_start(mut self, function: impl FnMut(&mut Self) + 'static) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            behaviour.push(Click::new(MouseButton::Left, MouseAction::DragStarted, function));
+        }
+        self
+    }
+
+    /// Called every frame of the drag with how far the mouse moved since the last one.
+    #[track_caller]
+    fn on_drag(mut self, mut function: impl FnMut(&mut Self, (isize, isize)) + 'static) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            behaviour.push(Click::new(MouseButton::Left, MouseAction::Dragged, move |widget| {
+                function(widget, ctx().drag.delta)
+            }));
+        }
+        self
+    }
+
+    #[track_caller]
+    fn on_drag_end(mut self, function: impl FnMut(&mut Self) + 'static) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            behaviour.push(Click::new(MouseButton::Left, MouseAction::DragEnded, function));
+        }
+        self
+    }
+
+    /// Given to the widget the drag is dropped on, see `on_drop`.
+    #[track_caller]
+    fn drag_payload<P: Any + Send + Sync + Clone>(mut self, payload: P) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            behaviour.push(Click::new(MouseButton::Left, MouseAction::DragStarted, move |_| {
+                ctx().drag.payload = Some(Arc::new(payload.clone()))
+            }));
+        }
+        self
+    }
+
+    /// Draw a copy of the widget under the mouse while it's dragged, on top of everything else.
+    #[track_caller]
+    fn drag_preview(mut self) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            behaviour.push(Click::new(
+                MouseButton::Left,
+                MouseAction::Dragged,
+                |widget: &mut Self| {
+                    let drag = &mut ctx().drag;
+                    drag.preview = Some(Command {
+                        area: preview_area(drag, widget.area()),
+                        primative: widget.primative(),
+                    });
+                },
+            ));
+        }
+        self
+    }
+
+    /// Only payloads of type `P` that pass the check can be dropped onto the widget.
+    #[track_caller]
+    fn accepts<P: 'static>(mut self, mut function: impl FnMut(&P) -> bool + 'static) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            behaviour.push(Click::new(MouseButton::Left, Action::Accepts, move |_| {
+                let drag = &mut ctx().drag;
+                drag.accepted = drag.payload::<P>().is_some_and(&mut function);
+            }));
+        }
+        self
+    }
+
+    /// A drag with a payload of type `P` was let go on top of the widget.
+    #[track_caller]
+    fn on_drop<P: 'static>(mut self, mut function: impl FnMut(&mut Self, &P) + 'static) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            behaviour.push(Click::new(MouseButton::Left, MouseAction::Dropped, move |widget| {
+                let payload = ctx().drag.payload.clone();
+                if let Some(payload) = payload.as_deref().and_then(|p| p.downcast_ref::<P>()) {
+                    function(widget, payload);
+                }
+            }));
+        }
+        self
+    }
+
+    /// Can be focused with Tab and Shift-Tab or by clicking on it.
+    #[track_caller]
+    fn focusable(mut self) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            behaviour.push(Click::new(MouseButton::Left, Action::Focusable, |_| {}));
+        }
+        self
+    }
+
+    /// Move the focus to the widget every frame `focused` is set.
+    #[track_caller]
+    fn focused(mut self, focused: bool) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            let action = if focused {
+                Action::FocusRequested
+            } else {
+                Action::Focusable
+            };
+            behaviour.push(Click::new(MouseButton::Left, action, |_| {}));
+        }
+        self
+    }
+
+    /// Keys pressed while the widget has focus, return `true` when the key is handled.
+    /// Keys that aren't handled go to the containers around the widget, see `DeferContainer::on_key`.
+    #[track_caller]
+    fn on_key(mut self, mut function: impl FnMut(&mut Self, &Key, &Modifiers) -> bool + 'static) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            behaviour.push(Click::new(MouseButton::Left, Action::KeyPressed, move |widget| {
+                ctx().focus.handle(|key, modifiers| function(widget, key, modifiers))
+            }));
+        }
+        self
+    }
+
+    /// Only runs while the widget has focus, before any shortcuts on the context.
+    #[track_caller]
+    fn shortcut(mut self, shortcut: Shortcut, mut function: impl FnMut(&mut Self) + 'static) -> Self {
+        if let Some(behaviour) = self.behaviour() {
+            //Checked against the shortcuts on the context, see `Shortcuts::conflicts`.
+            let drawn = shortcut.clone();
+            behaviour.push(Click::new(MouseButton::Left, Action::Drawn, move |_| {
+                ctx().shortcuts.drawn(&drawn)
+            }));
+            behaviour.push(Click::new(MouseButton::Left, Action::KeyPressed, move |widget| {
+                let ctx = ctx();
+                for _ in 0..ctx.shortcuts.handle(&shortcut, &mut ctx.focus.keys) {
+                    function(widget);
+                }
+            }));
+        }
+        self
+    }
+
+    /// The mouse is on top of the widget and no other widget is drawn over it.
+    /// Widgets without any behaviour are told apart by where this is called.
+    #[track_caller]
+    fn hovered(&mut self) -> bool {
+        let location = Location::caller();
+        let id = self
+            .widget_id()
+            .unwrap_or((location.file(), location.line(), location.column(), 0));
+        hovered(ctx(), id, self.area())
+    }
+
     /// The user's cusor has been clicked and released on top of a widget.
     fn clicked(&mut self, button: MouseButton) -> bool {
         clicked(ctx(), self.area(), button)
@@ -133,55 +403,46 @@ where
         self
     }
 
+    //Widgets that aren't in a container are relative to the window.
     fn x<U: Into<Unit>>(mut self, x: U) -> Self {
-        let area = self.area_mut().unwrap();
-        match x.into() {
-            Unit::Px(px) => {
-                area.x = px;
-            }
-            Unit::Em(_) => todo!(),
-            Unit::Percentage(p) => {
-                todo!();
-                // let percentage = p as f32 / 100.0;
-                // area.x = ((self.parent_area.width as f32 * percentage)
-                //     - (self.area.width as f32 / 2.0))
-                //     .round() as i32;
-            }
-        }
+        let x = x.into().resolve(viewport().width, self.em_size());
+        self.area_mut().unwrap().x = x;
         self
     }
     fn y<U: Into<Unit>>(mut self, y: U) -> Self {
-        let area = self.area_mut().unwrap();
-        match y.into() {
-            Unit::Px(px) => {
-                self.area_mut().unwrap().y = px;
-                // self.area.y = px as i32;
-            }
-            Unit::Em(_) => todo!(),
-            Unit::Percentage(_) => todo!(),
-        }
+        let y = y.into().resolve(viewport().height, self.em_size());
+        self.area_mut().unwrap().y = y;
         self
     }
+    /// Percentages are resolved again by containers with a fixed size.
     fn width<U: Into<Unit>>(mut self, length: U) -> Self {
-        let area = self.area_mut().unwrap();
-        match length.into() {
-            Unit::Px(px) => {
-                area.width = px;
-            }
-            Unit::Em(_) => todo!(),
-            Unit::Percentage(_) => todo!(),
+        let length = length.into();
+        let width = length.resolve(viewport().width, self.em_size());
+        if let Some(item) = self.item_mut() {
+            item.relative_width = match length {
+                Unit::Percentage(p) => Some(p),
+                _ => None,
+            };
         }
+        let item = self.item();
+        let area = self.area_mut().unwrap();
+        area.width = width;
+        item.constrain(area, true);
         self
     }
     fn height<U: Into<Unit>>(mut self, length: U) -> Self {
-        let area = self.area_mut().unwrap();
-        match length.into() {
-            Unit::Px(px) => {
-                area.height = px;
-            }
-            Unit::Em(_) => todo!(),
-            Unit::Percentage(_) => todo!(),
+        let length = length.into();
+        let height = length.resolve(viewport().height, self.em_size());
+        if let Some(item) = self.item_mut() {
+            item.relative_height = match length {
+                Unit::Percentage(p) => Some(p),
+                _ => None,
+            };
         }
+        let item = self.item();
+        let area = self.area_mut().unwrap();
+        area.height = height;
+        item.constrain(area, false);
         self
     }
     fn w<U: Into<Unit>>(self, width: U) -> Self {
@@ -190,35 +451,197 @@ where
     fn h<U: Into<Unit>>(self, width: U) -> Self {
         self.height(width)
     }
+    /// The widget won't shrink below this width, percentages are of the window.
+    fn min_w<U: Into<Unit>>(self, length: U) -> Self {
+        let length = length.into().resolve(viewport().width, self.em_size());
+        self.set_constraint(|item| item.min_width = Some(length), true)
+    }
+    /// The widget won't grow past this width, percentages are of the window.
+    fn max_w<U: Into<Unit>>(self, length: U) -> Self {
+        let length = length.into().resolve(viewport().width, self.em_size());
+        self.set_constraint(|item| item.max_width = Some(length), true)
+    }
+    fn min_h<U: Into<Unit>>(self, length: U) -> Self {
+        let length = length.into().resolve(viewport().height, self.em_size());
+        self.set_constraint(|item| item.min_height = Some(length), false)
+    }
+    fn max_h<U: Into<Unit>>(self, length: U) -> Self {
+        let length = length.into().resolve(viewport().height, self.em_size());
+        self.set_constraint(|item| item.max_height = Some(length), false)
+    }
+    /// Width divided by height, the height follows the width unless the height was set last.
+    fn aspect_ratio(self, ratio: f32) -> Self {
+        self.set_constraint(|item| item.aspect_ratio = Some(ratio), true)
+    }
+    #[doc(hidden)]
+    fn set_constraint(mut self, f: impl FnOnce(&mut FlexItem), horizontal: bool) -> Self {
+        let Some(item) = self.item_mut() else {
+            return self;
+        };
+        f(item);
+        let item = *item;
+        if let Some(area) = self.area_mut() {
+            item.constrain(area, horizontal);
+        }
+        self
+    }
     //Swizzle 😏
     fn wh<U: Into<Unit> + Copy>(self, value: U) -> Self {
         self.width(value).height(value)
     }
+    /// Distance from the top of the container, or the window when there isn't one.
     fn top<U: Into<Unit>>(self, top: U) -> Self {
-        self.y(top)
+        let (top, relative) = self.inset_length(top.into(), false);
+        self.set_inset(|inset| (inset.top, inset.relative_top) = (Some(top), relative))
     }
+    /// Distance from the left of the container, or the window when there isn't one.
     fn left<U: Into<Unit>>(self, left: U) -> Self {
-        self.x(left)
+        let (left, relative) = self.inset_length(left.into(), true);
+        self.set_inset(|inset| (inset.left, inset.relative_left) = (Some(left), relative))
+    }
+    /// Distance from the right of the container, or the window when there isn't one.
+    fn right<U: Into<Unit>>(self, right: U) -> Self {
+        let (right, relative) = self.inset_length(right.into(), true);
+        self.set_inset(|inset| (inset.right, inset.relative_right) = (Some(right), relative))
+    }
+    /// Distance from the bottom of the container, or the window when there isn't one.
+    fn bottom<U: Into<Unit>>(self, bottom: U) -> Self {
+        let (bottom, relative) = self.inset_length(bottom.into(), false);
+        self.set_inset(|inset| (inset.bottom, inset.relative_bottom) = (Some(bottom), relative))
+    }
+    /// Percentages are kept so they can be resolved against the parent, see `Inset::resolve`.
+    #[doc(hidden)]
+    fn inset_length(&self, length: Unit, horizontal: bool) -> (usize, Option<f32>) {
+        let window = main_cross(viewport(), horizontal).0;
+        let relative = match length {
+            Unit::Percentage(p) => Some(p),
+            _ => None,
+        };
+        (length.resolve(window, self.em_size()), relative)
+    }
+    /// The widget is only placed once it's laid out, or against the window by `draw_widget`.
+    #[doc(hidden)]
+    fn set_inset(mut self, f: impl FnOnce(&mut Inset)) -> Self {
+        if let Some(item) = self.item_mut() {
+            f(&mut item.inset);
+        }
+        self
     }
-    fn right<U: Into<Unit>>(mut self, length: U) -> Self {
-        match length.into() {
-            Unit::Px(px) => todo!(),
-            Unit::Em(_) => todo!(),
-            Unit::Percentage(_) => todo!(),
+    /// Take the widget out of the container's layout and place it with `left`, `top`, `right` and `bottom`.
+    fn absolute(mut self) -> Self {
+        if let Some(item) = self.item_mut() {
+            item.position = Position::Absolute;
         }
         self
     }
-    fn bottom<U: Into<Unit>>(mut self, length: U) -> Self {
-        match length.into() {
-            Unit::Px(px) => todo!(),
-            Unit::Em(_) => todo!(),
-            Unit::Percentage(_) => todo!(),
+    /// Lay the widget out as normal and then move it by `left`, `top`, `right` and `bottom`.
+    fn relative(mut self) -> Self {
+        if let Some(item) = self.item_mut() {
+            item.position = Position::Relative;
         }
         self
     }
     fn pos<U: Into<Unit>>(self, x: U, y: U, width: U, height: U) -> Self {
         self.x(x).y(y).width(width).height(height)
     }
+    /// Share of the free space this widget takes when the container is larger than it's content.
+    fn grow(mut self, grow: f32) -> Self {
+        if let Some(item) = self.item_mut() {
+            item.grow = grow;
+        }
+        self
+    }
+    /// Share of the overflow this widget gives up when the container is too small, defaults to 1.
+    fn shrink(mut self, shrink: f32) -> Self {
+        if let Some(item) = self.item_mut() {
+            item.shrink = shrink;
+        }
+        self
+    }
+    /// Space kept clear around the widget inside a `h!` or `v!`.
+    fn margin(mut self, margin: usize) -> Self {
+        if let Some(item) = self.item_mut() {
+            item.margin = Margin::new(margin, margin, margin, margin);
+        }
+        self
+    }
+    fn left_margin(mut self, margin: usize) -> Self {
+        if let Some(item) = self.item_mut() {
+            item.margin.left = margin;
+        }
+        self
+    }
+    fn top_margin(mut self, margin: usize) -> Self {
+        if let Some(item) = self.item_mut() {
+            item.margin.top = margin;
+        }
+        self
+    }
+    fn right_margin(mut self, margin: usize) -> Self {
+        if let Some(item) = self.item_mut() {
+            item.margin.right = margin;
+        }
+        self
+    }
+    fn bottom_margin(mut self, margin: usize) -> Self {
+        if let Some(item) = self.item_mut() {
+            item.margin.bottom = margin;
+        }
+        self
+    }
+    /// The column of a `grid!` the widget is placed in, starting from zero.
+    fn column(mut self, column: usize) -> Self {
+        if let Some(item) = self.item_mut() {
+            item.cell.column = Some(column);
+        }
+        self
+    }
+    /// The row of a `grid!` the widget is placed in, starting from zero.
+    fn row(mut self, row: usize) -> Self {
+        if let Some(item) = self.item_mut() {
+            item.cell.row = Some(row);
+        }
+        self
+    }
+    fn column_span(mut self, columns: usize) -> Self {
+        if let Some(item) = self.item_mut() {
+            item.cell.column_span = columns;
+        }
+        self
+    }
+    fn row_span(mut self, rows: usize) -> Self {
+        if let Some(item) = self.item_mut() {
+            item.cell.row_span = rows;
+        }
+        self
+    }
+    /// Size on the main axis before growing or shrinking, the width or height is used otherwise.
+    /// Percentages are of the container's main size and need the container to have a fixed size.
+    fn basis<U: Into<Unit>>(mut self, basis: U) -> Self {
+        let basis = basis.into();
+        let em = self.em_size();
+        if let Some(item) = self.item_mut() {
+            (item.basis, item.relative_basis) = match basis {
+                Unit::Percentage(p) => (None, Some(p)),
+                _ => (Some(basis.resolve(viewport().width, em)), None),
+            };
+        }
+        self
+    }
+    /// Alignment on the cross axis of a container, or vertically in a `stack!` and `grid!`.
+    fn align_self(mut self, align: AlignItems) -> Self {
+        if let Some(item) = self.item_mut() {
+            item.align_self = Some(align);
+        }
+        self
+    }
+    /// Horizontal alignment in a `stack!` and `grid!`.
+    fn justify_self(mut self, justify: AlignItems) -> Self {
+        if let Some(item) = self.item_mut() {
+            item.justify_self = Some(justify);
+        }
+        self
+    }
 }
 
 impl<T: Widget> Widget for &mut [T] {
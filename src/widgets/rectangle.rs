@@ -5,6 +5,7 @@ pub const fn rect() -> Rectangle {
         area: Rect::new(0, 0, 10, 10),
         bg: white(),
         radius: 0,
+        item: FlexItem::new(),
     }
 }
 
@@ -12,6 +13,7 @@ pub const fn rect() -> Rectangle {
 pub struct Rectangle {
     pub area: Rect,
     pub radius: usize,
+    pub item: FlexItem,
     bg: Color,
 }
 
@@ -36,6 +38,14 @@ impl Widget for Rectangle {
     fn area_mut(&mut self) -> Option<&mut Rect> {
         Some(&mut self.area)
     }
+
+    fn item(&self) -> FlexItem {
+        self.item
+    }
+
+    fn item_mut(&mut self) -> Option<&mut FlexItem> {
+        Some(&mut self.item)
+    }
 }
 
 impl Style for Rectangle {
@@ -0,0 +1,227 @@
+//! A paragraph made out of differently styled spans.
+//!
+//! ```rs
+//! rich_text([span("error: ").red().underline(), span("file not found")]).w(300)
+//! ```
+use crate::*;
+use fontdue::Font;
+
+pub fn span<'a>(text: impl Into<Cow<'a, str>>) -> Span<'a> {
+    Span {
+        text: text.into(),
+        color: white(),
+        font_size: default_font_size(),
+        font: None,
+        underline: false,
+        strikethrough: false,
+        highlight: None,
+    }
+}
+
+#[derive(Clone)]
+pub struct Span<'a> {
+    pub text: Cow<'a, str>,
+    pub color: Color,
+    pub font_size: usize,
+    /// Uses the default font when `None`.
+    pub font: Option<&'static Font>,
+    pub underline: bool,
+    pub strikethrough: bool,
+    /// Background color drawn behind the glyphs.
+    pub highlight: Option<Color>,
+}
+
+impl<'a> Span<'a> {
+    pub fn font_size(mut self, font_size: usize) -> Self {
+        self.font_size = font_size;
+        self
+    }
+    pub fn font(mut self, font: &'static Font) -> Self {
+        self.font = Some(font);
+        self
+    }
+    pub fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+    pub fn strikethrough(mut self) -> Self {
+        self.strikethrough = true;
+        self
+    }
+    pub fn highlight(mut self, color: Color) -> Self {
+        self.highlight = Some(color);
+        self
+    }
+    pub fn font_or_default(&self) -> &'static Font {
+        self.font.or(default_font()).unwrap()
+    }
+    pub fn into_owned(self) -> Span<'static> {
+        Span {
+            text: Cow::Owned(self.text.into_owned()),
+            color: self.color,
+            font_size: self.font_size,
+            font: self.font,
+            underline: self.underline,
+            strikethrough: self.strikethrough,
+            highlight: self.highlight,
+        }
+    }
+}
+
+impl<'a> Style for Span<'a> {
+    fn bg(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+impl<'a> std::fmt::Debug for Span<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Span")
+            .field("text", &self.text)
+            .field("color", &self.color)
+            .field("font_size", &self.font_size)
+            .field("underline", &self.underline)
+            .field("strikethrough", &self.strikethrough)
+            .field("highlight", &self.highlight)
+            .finish()
+    }
+}
+
+/// Break the spans into lines, wrapping on spaces when a line is wider than `max_width`.
+/// Every line is as tall as the largest span on it.
+pub fn layout_spans(spans: &[Span], max_width: Option<usize>, line_height: usize) -> Vec<LineBox> {
+    let runs: Vec<Run> = spans
+        .iter()
+        .map(|span| Run {
+            text: &span.text,
+            font: span.font_or_default(),
+            font_size: span.font_size as f32,
+        })
+        .collect();
+
+    layout_runs(&runs, max_width, line_height)
+}
+
+pub fn rich_text<'a>(spans: impl IntoIterator<Item = Span<'a>>) -> RichText<'a> {
+    RichText {
+        spans: spans.into_iter().collect(),
+        line_height: None,
+        align: TextAlign::Left,
+        max_width: None,
+        area: Rect::default(),
+        item: FlexItem::new(),
+    }
+    .calculate_area()
+}
+
+#[derive(Debug, Clone)]
+pub struct RichText<'a> {
+    pub spans: Vec<Span<'a>>,
+    pub line_height: Option<usize>,
+    pub align: TextAlign,
+    /// Set with `width()`, lines longer than this are wrapped.
+    pub max_width: Option<usize>,
+    pub area: Rect,
+    pub item: FlexItem,
+}
+
+impl<'a> RichText<'a> {
+    pub fn span(mut self, span: Span<'a>) -> Self {
+        self.spans.push(span);
+        self.calculate_area()
+    }
+    pub fn line_height(mut self, line_height: usize) -> Self {
+        self.line_height = Some(line_height);
+        self.calculate_area()
+    }
+    pub fn align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+    pub fn lines(&self) -> Vec<LineBox> {
+        layout_spans(&self.spans, self.max_width, self.line_height.unwrap_or_default())
+    }
+    fn calculate_area(mut self) -> Self {
+        let lines = self.lines();
+        let width = lines.iter().map(|l| l.width).fold(0.0, f32::max).ceil() as usize;
+
+        self.area.width = self.max_width.unwrap_or(width);
+        self.area.height = lines.last().map(|l| l.y + l.height()).unwrap_or(0);
+        self
+    }
+}
+
+impl<'a> Widget for RichText<'a> {
+    fn primative(&self) -> Primative {
+        Primative::RichText(
+            self.spans.iter().cloned().map(Span::into_owned).collect(),
+            self.line_height.unwrap_or_default(),
+            self.align,
+        )
+    }
+
+    #[inline]
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn area_mut(&mut self) -> Option<&mut Rect> {
+        Some(&mut self.area)
+    }
+
+    fn item(&self) -> FlexItem {
+        self.item
+    }
+
+    fn item_mut(&mut self) -> Option<&mut FlexItem> {
+        Some(&mut self.item)
+    }
+
+    fn baseline(&self) -> Option<usize> {
+        self.lines().first().map(LineBox::baseline)
+    }
+
+    fn em_size(&self) -> usize {
+        self.spans.first().map(|s| s.font_size).unwrap_or(default_font_size())
+    }
+
+    //The width changes where the lines wrap, so the height must be recalculated.
+    fn width<U: Into<Unit>>(mut self, length: U) -> Self {
+        self.max_width = Some(length.into().resolve(viewport().width, self.em_size()));
+        self.calculate_area()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn wrapping() {
+        let spans = [span("one two "), span("three").font_size(32)];
+        let lines = layout_spans(&spans, None, 0);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].fragments.len(), 2);
+
+        //The larger span sets the height of the line.
+        let (ascent, _) = line_metrics(default_font().unwrap(), 32.0);
+        assert_eq!(lines[0].ascent, ascent);
+
+        let width = line_width(default_font().unwrap(), "one two ", default_font_size() as f32);
+        let lines = layout_spans(&spans, Some(width as usize), 0);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].fragments[0].run, 1);
+        assert_eq!(lines[1].y, lines[0].height());
+    }
+
+    #[test]
+    fn new_lines() {
+        let spans = [span("first\nsecond")];
+        let lines = layout_spans(&spans, None, 0);
+        assert_eq!(lines.len(), 2);
+
+        let second = lines[1].fragments[0];
+        assert_eq!(&spans[0].text[second.start..second.end], "second");
+    }
+}
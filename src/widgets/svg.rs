@@ -11,6 +11,7 @@ pub fn svg<P: AsRef<std::path::Path>>(path: P, width: usize, height: usize, scal
 pub struct Svg {
     pub pixmap: Pixmap,
     pub area: Rect,
+    pub item: FlexItem,
 }
 
 impl Svg {
@@ -22,6 +23,7 @@ impl Svg {
         Self {
             area: Rect::new(0, 0, pixmap.width() as usize, pixmap.height() as usize),
             pixmap,
+            item: FlexItem::new(),
         }
     }
 }
@@ -41,4 +43,12 @@ impl Widget for Svg {
     fn area_mut(&mut self) -> Option<&mut Rect> {
         Some(&mut self.area)
     }
+
+    fn item(&self) -> FlexItem {
+        self.item
+    }
+
+    fn item_mut(&mut self) -> Option<&mut FlexItem> {
+        Some(&mut self.item)
+    }
 }
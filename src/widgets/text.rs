@@ -1,23 +1,30 @@
 use crate::*;
 use fontdue::*;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    OnceLock,
+};
 
 pub const FONT: &[u8] = include_bytes!("../../fonts/JetBrainsMono.ttf");
 
 static mut DEFAULT_FONT_SIZE: AtomicUsize = AtomicUsize::new(18);
-static mut DEFAULT_FONT: Option<Font> = None;
+static DEFAULT_FONT: OnceLock<Font> = OnceLock::new();
 
 //TODO: This is slow
 pub fn load_default_font() {
-    set_default_font(fontdue::Font::from_bytes(FONT, fontdue::FontSettings::default()).unwrap());
+    DEFAULT_FONT.get_or_init(|| fontdue::Font::from_bytes(FONT, fontdue::FontSettings::default()).unwrap());
 }
 
+/// The font is loaded the first time it's used, so text can be measured without a window.
 pub fn default_font() -> Option<&'static Font> {
-    unsafe { DEFAULT_FONT.as_ref() }
+    load_default_font();
+    DEFAULT_FONT.get()
 }
 
-pub fn set_default_font(font: Font) {
-    unsafe { DEFAULT_FONT = Some(font) };
+/// Only works before the default font is first used, otherwise the font is given back.
+/// Text measured on another thread could still be holding the old font.
+pub fn set_default_font(font: Font) -> Result<(), Font> {
+    DEFAULT_FONT.set(font)
 }
 
 pub fn default_font_size() -> usize {
@@ -28,13 +35,399 @@ pub fn set_default_font_size(font_size: usize) {
     unsafe { DEFAULT_FONT_SIZE.store(font_size, Ordering::Relaxed) }
 }
 
+/// Horizontal alignment of each line inside the text area.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretch the spaces so every line except the last fills the width.
+    Justify,
+}
+
+/// Vertical alignment of the block of lines inside the text area.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Center,
+    Bottom,
+}
+
+/// Everything except the font that's needed to draw a block of text, see `Context::draw_text_aligned`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    pub font_size: usize,
+    /// Extra space between lines, zero is fine.
+    pub line_height: usize,
+    pub color: Color,
+    pub align: TextAlign,
+    pub vertical_align: VerticalAlign,
+}
+
+/// (ascent, descent) of the font at this size.
+/// The descent is negative, so `ascent - descent` is the height of a line.
+pub fn line_metrics(font: &Font, font_size: f32) -> (f32, f32) {
+    match font.horizontal_line_metrics(font_size) {
+        Some(metrics) => (metrics.ascent, metrics.descent),
+        //Fonts without a hhea table, just guess.
+        None => (font_size, 0.0),
+    }
+}
+
+/// Height of a single line without any extra line height.
+pub fn line_box(font: &Font, font_size: f32) -> usize {
+    let (ascent, descent) = line_metrics(font, font_size);
+    (ascent - descent).ceil() as usize
+}
+
+/// Sum of the advance widths for a single line of text.
+pub fn line_width(font: &Font, line: &str, font_size: f32) -> f32 {
+    //Joined arabic letters and ligatures don't have the same width as the isolated forms.
+    shape_chars(line)
+        .into_iter()
+        .map(|(_, c)| glyph_advance(font, c, font_size))
+        .sum()
+}
+
+/// The starting x position of a line and the extra space added to each ' ' character.
+pub fn align_line(align: TextAlign, width: usize, line_width: f32, spaces: usize, last_line: bool) -> (f32, f32) {
+    let free = (width as f32 - line_width).max(0.0);
+    match align {
+        TextAlign::Left => (0.0, 0.0),
+        TextAlign::Center => ((free / 2.0).floor(), 0.0),
+        TextAlign::Right => (free.floor(), 0.0),
+        TextAlign::Justify if last_line || spaces == 0 => (0.0, 0.0),
+        TextAlign::Justify => (0.0, free / spaces as f32),
+    }
+}
+
+/// Offset from the top of the area to the top of the first line.
+pub fn align_block(align: VerticalAlign, height: usize, block_height: usize) -> usize {
+    let free = height.saturating_sub(block_height);
+    match align {
+        VerticalAlign::Top => 0,
+        VerticalAlign::Center => free / 2,
+        VerticalAlign::Bottom => free,
+    }
+}
+
+/// Width and height of a block of text.
+/// The height only depends on the font and number of lines, never the glyphs.
+pub fn text_size(font: &Font, text: &str, font_size: usize, line_height: usize) -> (usize, usize) {
+    let line = line_box(font, font_size as f32);
+    let mut width: f32 = 0.0;
+    let mut lines = 0;
+
+    for l in text.lines() {
+        width = width.max(line_width(font, l, font_size as f32));
+        lines += 1;
+    }
+
+    if lines == 0 {
+        return (0, 0);
+    }
+
+    (width.ceil() as usize, lines * line + (lines - 1) * line_height)
+}
+
+/// A run of text with a single font and size.
+#[derive(Clone, Copy)]
+pub struct Run<'a> {
+    pub text: &'a str,
+    pub font: &'a Font,
+    pub font_size: f32,
+}
+
+/// Part of a run that sits on one line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fragment {
+    /// Index into the runs.
+    pub run: usize,
+    /// Byte range into the text of the run.
+    pub start: usize,
+    pub end: usize,
+    pub x: f32,
+    pub width: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LineBox {
+    pub fragments: Vec<Fragment>,
+    /// Offset from the top of the paragraph.
+    pub y: usize,
+    pub width: f32,
+    pub ascent: f32,
+    pub descent: f32,
+    /// Wrapped lines use the direction of the whole paragraph.
+    pub direction: TextDirection,
+}
+
+impl LineBox {
+    pub fn height(&self) -> usize {
+        (self.ascent - self.descent).ceil() as usize
+    }
+    /// Offset from the top of the paragraph to the baseline.
+    pub fn baseline(&self) -> usize {
+        self.y + self.ascent.ceil() as usize
+    }
+    fn grow(&mut self, ascent: f32, descent: f32) {
+        self.ascent = self.ascent.max(ascent);
+        self.descent = self.descent.min(descent);
+    }
+}
+
+/// Break the runs into lines, wrapping on spaces when a line is wider than `max_width`.
+/// Every line is as tall as the largest run on it.
+pub fn layout_runs(runs: &[Run], max_width: Option<usize>, line_height: usize) -> Vec<LineBox> {
+    let max_width = max_width.map(|w| w as f32);
+    let mut lines = Vec::new();
+    let mut line = LineBox::default();
+    //Paragraphs can span multiple runs, the direction is set once the paragraph ends.
+    let mut paragraph = String::new();
+    let mut paragraph_start = 0;
+    let end_paragraph = |lines: &mut Vec<LineBox>, paragraph: &mut String, start: usize| {
+        let direction = paragraph_direction(paragraph);
+        for line in &mut lines[start..] {
+            line.direction = direction;
+        }
+        paragraph.clear();
+    };
+
+    for (i, run) in runs.iter().enumerate() {
+        let font = run.font;
+        let size = run.font_size;
+        let (ascent, descent) = line_metrics(font, size);
+        let mut start = 0;
+
+        for (n, segment) in run.text.split('\n').enumerate() {
+            if n > 0 {
+                line.grow(ascent, descent);
+                lines.push(std::mem::take(&mut line));
+                end_paragraph(&mut lines, &mut paragraph, paragraph_start);
+                paragraph_start = lines.len();
+            }
+            paragraph.push_str(segment);
+
+            for word in segment.split_inclusive(' ') {
+                let width = line_width(font, word, size);
+                //Trailing spaces are allowed to hang off the end of the line.
+                let visible = line_width(font, word.trim_end_matches(' '), size);
+
+                if let Some(max_width) = max_width {
+                    if !line.fragments.is_empty() && line.width + visible > max_width {
+                        lines.push(std::mem::take(&mut line));
+                    }
+                }
+
+                match line.fragments.last_mut() {
+                    Some(f) if f.run == i && f.end == start => {
+                        f.end += word.len();
+                        f.width += width;
+                    }
+                    _ => line.fragments.push(Fragment {
+                        run: i,
+                        start,
+                        end: start + word.len(),
+                        x: line.width,
+                        width,
+                    }),
+                }
+
+                line.width += width;
+                line.grow(ascent, descent);
+                start += word.len();
+            }
+
+            //Skip over the new line.
+            start += 1;
+        }
+    }
+
+    if !line.fragments.is_empty() || line.ascent != 0.0 {
+        lines.push(line);
+    }
+    end_paragraph(&mut lines, &mut paragraph, paragraph_start);
+
+    let mut y = 0;
+    for line in &mut lines {
+        line.y = y;
+        y += line.height() + line_height;
+    }
+
+    lines
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphPosition {
+    pub char: char,
+    /// Byte offset into the text.
+    pub index: usize,
+    /// Index into `TextLayout::lines`.
+    pub line: usize,
+    /// Pen position relative to the start of the line.
+    pub x: f32,
+    /// Top of the line.
+    pub y: usize,
+    pub advance: f32,
+    /// Number of bytes the glyph covers, ligatures cover more than one character.
+    pub len: usize,
+    /// Right to left glyphs have the caret on their right side.
+    pub rtl: bool,
+}
+
+/// The result of `measure_text`.
+/// Lines are left aligned, use `align_line` to offset them.
+/// Glyphs are stored in visual order, left to right on each line.
+#[derive(Debug, Clone, Default)]
+pub struct TextLayout {
+    pub width: usize,
+    pub height: usize,
+    pub lines: Vec<LineBox>,
+    pub glyphs: Vec<GlyphPosition>,
+}
+
+impl TextLayout {
+    /// Byte offset of the start of each line.
+    /// Empty lines don't have any glyphs, they start after the previous line and the new line.
+    pub fn line_starts(&self) -> Vec<usize> {
+        let mut starts = Vec::with_capacity(self.lines.len());
+        let mut end = None;
+        for line in &self.lines {
+            let start = match line.fragments.first() {
+                Some(first) => first.start,
+                None => end.map(|end| end + 1).unwrap_or(0),
+            };
+            starts.push(start);
+            end = Some(line.fragments.last().map(|f| f.end).unwrap_or(start));
+        }
+        starts
+    }
+
+    /// The byte offset of the caret closest to the point.
+    pub fn hit_test(&self, x: f32, y: f32) -> usize {
+        let Some(last) = self.lines.len().checked_sub(1) else {
+            return 0;
+        };
+
+        let line = self
+            .lines
+            .iter()
+            .position(|l| y < (l.y + l.height()) as f32)
+            .unwrap_or(last);
+
+        let mut index = self.line_starts()[line];
+
+        for glyph in self.glyphs.iter().filter(|g| g.line == line) {
+            let (before, after) = match glyph.rtl {
+                false => (glyph.index, glyph.index + glyph.len),
+                true => (glyph.index + glyph.len, glyph.index),
+            };
+            if x < glyph.x + glyph.advance / 2.0 {
+                return before;
+            }
+            index = after;
+        }
+
+        index
+    }
+
+    /// A one pixel wide caret in front of the byte offset.
+    pub fn caret(&self, index: usize) -> Rect {
+        let glyph = self.glyphs.iter().find(|g| g.index == index);
+        //There's no glyph for the new line, so the end of a line is after it's last glyph.
+        let end = self.glyphs.iter().find(|g| g.index + g.len == index);
+        let (line, x) = match (glyph, end) {
+            (Some(glyph), _) if glyph.rtl => (glyph.line, glyph.x + glyph.advance),
+            (Some(glyph), _) => (glyph.line, glyph.x),
+            (None, Some(end)) if end.rtl => (end.line, end.x),
+            (None, Some(end)) => (end.line, end.x + end.advance),
+            //Empty lines, use the last line that starts before the offset.
+            (None, None) => {
+                let line = self
+                    .line_starts()
+                    .iter()
+                    .rposition(|start| *start <= index)
+                    .unwrap_or(0);
+                match self.glyphs.iter().rfind(|g| g.line == line && g.index < index) {
+                    //Past the end of the text.
+                    Some(last) => (line, last.x + last.advance),
+                    None => (line, 0.0),
+                }
+            }
+        };
+
+        match self.lines.get(line) {
+            Some(l) => Rect::new(x.round() as usize, l.y, 1, l.height()),
+            None => Rect::new(0, 0, 1, 0),
+        }
+    }
+}
+
+/// Lay out text without a window or context.
+/// Lines are wrapped on spaces when `max_width` is set.
+pub fn measure_text(text: &str, font: &Font, font_size: usize, max_width: Option<usize>) -> TextLayout {
+    let size = font_size as f32;
+    let lines = layout_runs(
+        &[Run {
+            text,
+            font,
+            font_size: size,
+        }],
+        max_width,
+        0,
+    );
+
+    let mut glyphs = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let (Some(first), Some(last)) = (line.fragments.first(), line.fragments.last()) else {
+            continue;
+        };
+        let start = first.start;
+        let line_text = &text[start..last.end];
+        let direction = line.direction;
+
+        //Byte offsets of each shaped character, used to find how many bytes it covers.
+        let mut offsets: Vec<usize> = shape_chars(line_text).into_iter().map(|(i, _)| i).collect();
+        offsets.push(line_text.len());
+
+        let mut x = first.x;
+        for (index, char, rtl) in visual_chars(line_text, direction) {
+            let advance = glyph_advance(font, char, size);
+            let next = offsets.binary_search(&index).unwrap() + 1;
+            glyphs.push(GlyphPosition {
+                char,
+                index: start + index,
+                line: i,
+                x,
+                y: line.y,
+                advance,
+                len: offsets[next] - index,
+                rtl,
+            });
+            x += advance;
+        }
+    }
+
+    TextLayout {
+        width: lines.iter().map(|l| l.width).fold(0.0, f32::max).ceil() as usize,
+        height: lines.last().map(|l| l.y + l.height()).unwrap_or(0),
+        lines,
+        glyphs,
+    }
+}
+
 pub fn text<'a>(text: impl Into<Cow<'a, str>>) -> Text<'a> {
     Text {
         text: text.into(),
         color: white(),
         font_size: default_font_size(),
         line_height: None,
+        align: TextAlign::Left,
+        vertical_align: VerticalAlign::Top,
         area: Rect::default(),
+        item: FlexItem::new(),
         drawn: false,
     }
     .calculate_area()
@@ -46,8 +439,11 @@ pub struct Text<'a> {
     pub color: Color,
     pub font_size: usize,
     pub line_height: Option<usize>,
+    pub align: TextAlign,
+    pub vertical_align: VerticalAlign,
     //Used with the builder pattern, x(), y(), width(), etc...
     pub area: Rect,
+    pub item: FlexItem,
     pub drawn: bool,
 }
 
@@ -60,70 +456,23 @@ impl<'a> Text<'a> {
         self.line_height = Some(line_height);
         self.calculate_area()
     }
+    /// Only noticeable when the width is larger than the text.
+    pub fn align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+    /// Only noticeable when the height is larger than the text.
+    pub fn vertical_align(mut self, vertical_align: VerticalAlign) -> Self {
+        self.vertical_align = vertical_align;
+        self
+    }
+    //Text used to hug the glyphs, so two text widgets with the same y had different heights.
+    //Now the box is always `ascent - descent` tall for every line.
     fn calculate_area(mut self) -> Self {
-        let canvas_width = ctx().window.width();
         let font = default_font().unwrap();
-        let mut area = self.area;
-
-        //TODO: Two text widgets with same y value have different heights.
-        //Text needs to be aligned specifically over this y coordinate,
-        //and not based on the largest character.
-        let mut y: usize = area.y as usize;
-        let x = area.x as usize;
-
-        let mut max_x = 0;
-        let mut max_y = 0;
-        let line_height = self.line_height.unwrap_or_default();
-
-        'line: for line in self.text.lines() {
-            let mut glyph_x = x;
-
-            'char: for char in line.chars() {
-                let (metrics, _) = font.rasterize(char, self.font_size as f32);
-
-                let glyph_y = y as f32 - (metrics.height as f32 - metrics.advance_height) - metrics.ymin as f32;
-
-                for y in 0..metrics.height {
-                    for x in 0..metrics.width {
-                        //Should the text really be offset by the font size?
-                        //This allows the user to draw text at (0, 0).
-                        let offset = self.font_size as f32 + glyph_y + y as f32;
-
-                        //We can't render off of the screen, mkay?
-                        if offset < 0.0 {
-                            continue;
-                        }
-
-                        if max_x < x + glyph_x {
-                            max_x = x + glyph_x;
-                        }
-
-                        if max_y < offset as usize {
-                            max_y = offset as usize;
-                        }
-
-                        let i = x + glyph_x + canvas_width * offset as usize;
-                    }
-                }
-
-                glyph_x += metrics.advance_width as usize;
-
-                //TODO: Still not enough.
-                if glyph_x >= canvas_width {
-                    break 'line;
-                }
-            }
-
-            //CSS is probably line height * font size.
-            //1.2 is the default line height
-            //I'm guessing 1.0 is probably just adding the font size.
-            y += self.font_size + line_height;
-        }
-
-        area.height = (max_y + 1 - area.y);
-        area.width = (max_x + 1 - area.x);
-
-        self.area = area;
+        let (width, height) = text_size(font, &self.text, self.font_size, self.line_height.unwrap_or_default());
+        self.area.width = width;
+        self.area.height = height;
         self
     }
 }
@@ -137,7 +486,13 @@ impl<'a> Style for Text<'a> {
 
 impl<'a> Widget for Text<'a> {
     fn primative(&self) -> Primative {
-        Primative::Text(self.text.to_string(), self.font_size, self.color)
+        Primative::Text(
+            self.text.to_string(),
+            self.font_size,
+            self.color,
+            self.align,
+            self.vertical_align,
+        )
     }
 
     #[inline]
@@ -148,6 +503,25 @@ impl<'a> Widget for Text<'a> {
     fn area_mut(&mut self) -> Option<&mut Rect> {
         Some(&mut self.area)
     }
+
+    fn item(&self) -> FlexItem {
+        self.item
+    }
+
+    fn item_mut(&mut self) -> Option<&mut FlexItem> {
+        Some(&mut self.item)
+    }
+
+    fn em_size(&self) -> usize {
+        self.font_size
+    }
+
+    fn baseline(&self) -> Option<usize> {
+        let font = default_font().unwrap();
+        let (ascent, _) = line_metrics(font, self.font_size as f32);
+        let (_, height) = text_size(font, &self.text, self.font_size, self.line_height.unwrap_or_default());
+        Some(align_block(self.vertical_align, self.area.height, height) + ascent.ceil() as usize)
+    }
 }
 
 pub struct Atlas {
@@ -196,29 +570,183 @@ const LCD_FILTER: [u8; 5] = [0x08, 0x4D, 0x56, 0x4D, 0x08];
 // What in the fuck?
 // https://github.com/arkanis/gl-4.5-subpixel-text-rendering/blob/d770f0395f610d9fcc53319734069fe7fc4138b2/main.c#L626
 
-pub fn fontdue_subpixel(ctx: &mut Context, x: usize, y: usize) {
-    let font = fontdue::Font::from_bytes(FONT, fontdue::FontSettings::default()).unwrap();
-    let (metrics, bitmap) = font.rasterize_subpixel('g', 200.0);
+/// How glyphs are rasterized by `Context::draw_char`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TextRendering {
+    /// Single channel coverage, looks the same on every display.
+    #[default]
+    Grayscale,
+    /// LCD subpixel rendering for panels with RGB subpixel order.
+    Subpixel,
+    /// LCD subpixel rendering for panels with BGR subpixel order.
+    SubpixelBgr,
+}
+
+/// Run the FreeType default filter over the output of `Font::rasterize_subpixel`.
+/// Without this the glyphs have very obvious color fringes.
+///
+/// The filter bleeds two subpixels each way, so the output is one pixel wider on both sides.
+/// Returns the new width in pixels and the filtered `width * height * 3` coverage.
+pub fn lcd_filter(width: usize, height: usize, bitmap: &[u8]) -> (usize, Vec<u8>) {
+    let out_width = width + 2;
+    let mut out = vec![0u8; out_width * height * 3];
+
+    for y in 0..height {
+        let row = &bitmap[y * width * 3..(y + 1) * width * 3];
+        let out_row = &mut out[y * out_width * 3..(y + 1) * out_width * 3];
+
+        for (s, coverage) in out_row.iter_mut().enumerate() {
+            let mut sum = 0u32;
+            for (k, weight) in LCD_FILTER.iter().enumerate() {
+                //Shift back by the three padding subpixels and the two filter taps.
+                let i = s as isize + k as isize - 5;
+                if i >= 0 && (i as usize) < row.len() {
+                    sum += row[i as usize] as u32 * *weight as u32;
+                }
+            }
+            *coverage = (sum / 256) as u8;
+        }
+    }
 
-    let start_x = x;
-    let start_y = y;
+    (out_width, out)
+}
 
-    for y in 0..metrics.height {
-        for x in 0..metrics.width {
-            let i = ((start_y + y) * ctx.window.width() + start_x + x);
-            let j = (y * metrics.width + x) * 3;
+#[cfg(test)]
+mod tests {
+    use crate::*;
 
-            let r = bitmap[j];
-            let g = bitmap[j + 1];
-            let b = bitmap[j + 2];
+    #[test]
+    fn line_height() {
+        //The height no longer depends on the glyphs.
+        let a = text("a");
+        let g = text("g");
+        assert_eq!(a.area.height, g.area.height);
+        assert_eq!(a.baseline(), g.baseline());
 
-            ctx.window.buffer[i] = rgb(r, g, b).as_u32();
-        }
+        let two = text("a\ng");
+        assert_eq!(two.area.height, a.area.height * 2);
+    }
+
+    #[test]
+    fn measure() {
+        let font = default_font().unwrap();
+        let layout = measure_text("ab cd", font, 20, None);
+        assert_eq!(layout.lines.len(), 1);
+        assert_eq!(layout.glyphs.len(), 5);
+        assert_eq!(layout.height, line_box(font, 20.0));
+        assert!((layout.width as f32 - line_width(font, "ab cd", 20.0)).abs() < 1.0);
+
+        //Wrap after the space.
+        let width = line_width(font, "ab ", 20.0) as usize;
+        let layout = measure_text("ab cd", font, 20, Some(width));
+        assert_eq!(layout.lines.len(), 2);
+        assert_eq!(layout.glyphs[3].line, 1);
+        assert_eq!(layout.glyphs[3].x, 0.0);
+        assert_eq!(layout.height, line_box(font, 20.0) * 2);
+    }
+
+    #[test]
+    fn hit_test() {
+        let font = default_font().unwrap();
+        let layout = measure_text("ab\ncd", font, 20, None);
+        let b = layout.glyphs[1];
+
+        //Left half of the glyph puts the caret before it, right half after.
+        assert_eq!(layout.hit_test(b.x + 1.0, 1.0), 1);
+        assert_eq!(layout.hit_test(b.x + b.advance - 1.0, 1.0), 2);
+
+        //Second line.
+        let y = layout.lines[1].y as f32 + 1.0;
+        assert_eq!(layout.hit_test(0.0, y), 3);
+        assert_eq!(layout.hit_test(1000.0, y), 5);
+
+        let caret = layout.caret(4);
+        assert_eq!(caret.y, layout.lines[1].y);
+        assert_eq!(caret.x, layout.glyphs[2].advance.round() as usize);
+    }
+
+    #[test]
+    fn caret() {
+        let font = default_font().unwrap();
+        let layout = measure_text("ab\ncd\n\nef", font, 20, None);
+        let b = layout.glyphs[1];
+
+        //The end of a line stays on that line instead of moving to the start of the next.
+        let caret = layout.caret(2);
+        assert_eq!(caret.y, layout.lines[0].y);
+        assert_eq!(caret.x, (b.x + b.advance).round() as usize);
+
+        let d = layout.glyphs[3];
+        let caret = layout.caret(5);
+        assert_eq!(caret.y, layout.lines[1].y);
+        assert_eq!(caret.x, (d.x + d.advance).round() as usize);
+
+        //Start of the next line.
+        let caret = layout.caret(3);
+        assert_eq!((caret.x, caret.y), (0, layout.lines[1].y));
+
+        //Empty lines don't have a glyph to put the caret in front of.
+        let caret = layout.caret(6);
+        assert_eq!((caret.x, caret.y), (0, layout.lines[2].y));
+        assert_eq!(layout.line_starts(), vec![0, 3, 6, 7]);
+
+        //End of the text.
+        let f = layout.glyphs[5];
+        let caret = layout.caret(9);
+        assert_eq!(caret.y, layout.lines[3].y);
+        assert_eq!(caret.x, (f.x + f.advance).round() as usize);
+    }
+
+    #[test]
+    fn alignment() {
+        assert_eq!(align_line(TextAlign::Left, 100, 40.0, 0, true), (0.0, 0.0));
+        assert_eq!(align_line(TextAlign::Center, 100, 40.0, 0, true), (30.0, 0.0));
+        assert_eq!(align_line(TextAlign::Right, 100, 40.0, 0, true), (60.0, 0.0));
+        assert_eq!(align_line(TextAlign::Justify, 100, 40.0, 2, false), (0.0, 30.0));
+        assert_eq!(align_line(TextAlign::Justify, 100, 40.0, 2, true), (0.0, 0.0));
+
+        assert_eq!(align_block(VerticalAlign::Top, 100, 20), 0);
+        assert_eq!(align_block(VerticalAlign::Center, 100, 20), 40);
+        assert_eq!(align_block(VerticalAlign::Bottom, 100, 20), 80);
+    }
+
+    #[test]
+    fn lcd() {
+        //A single fully covered pixel.
+        let (width, filtered) = lcd_filter(1, 1, &[255, 255, 255]);
+        assert_eq!(width, 3);
+        assert_eq!(filtered.len(), 9);
+
+        //The filter is normalized so the coverage is only spread out, not lost.
+        let total: u32 = filtered.iter().map(|c| *c as u32).sum();
+        assert!((765 - 9..=765).contains(&total));
+
+        //Symmetric around the center pixel.
+        assert_eq!(
+            filtered[..3].iter().rev().collect::<Vec<_>>(),
+            filtered[6..].iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn baseline_row() {
+        let small = text("small").font_size(12);
+        let large = text("large").font_size(32);
+        let container = h!(text("small").font_size(12), text("large").font_size(32))
+            .align_baseline()
+            .build();
+
+        assert_eq!(container.baseline, large.baseline().unwrap());
+        assert!(container.area.height >= large.area.height);
+
+        //The small text is pushed down so the baselines match.
+        let shift = container.baseline - small.baseline().unwrap();
+        assert!(shift > 0);
     }
 }
 
 // #[cfg(test)]
-// mod tests {
+// mod benches {
 //     extern crate test;
 
 //     use super::*;